@@ -1,4 +1,4 @@
-use crate::opendal_builder;
+use crate::{opendal_builder, scheduler::TransferLimits};
 use opendal::{services::Gcs, Error, Operator};
 use serde::{Deserialize, Serialize};
 
@@ -45,4 +45,8 @@ impl TryInto<Operator> for GCSConfig {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GCSBucket {
     pub configuration: GCSConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<TransferLimits>,
+    #[serde(rename = "directoryMarkers", skip_serializing_if = "Option::is_none")]
+    pub directory_markers: Option<bool>,
 }
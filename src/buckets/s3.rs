@@ -1,7 +1,7 @@
 use opendal::{services::S3, Error, Operator};
 use serde::{Deserialize, Serialize};
 
-use crate::opendal_builder;
+use crate::{opendal_builder, scheduler::TransferLimits};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct S3Config {
@@ -46,4 +46,8 @@ impl TryInto<Operator> for S3Config {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct S3Bucket {
     pub configuration: S3Config,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<TransferLimits>,
+    #[serde(rename = "directoryMarkers", skip_serializing_if = "Option::is_none")]
+    pub directory_markers: Option<bool>,
 }
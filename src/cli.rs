@@ -1,5 +1,14 @@
 pub use clap::Parser;
-use clap::Subcommand;
+use clap::{Subcommand, ValueEnum};
+use remote_files::{
+    client::InventoryFormat,
+    util::{parse_age, parse_size, Column, SortBy, DEFAULT_COLUMNS},
+};
+use std::{
+    num::{NonZeroU64, NonZeroUsize},
+    path::PathBuf,
+    time::Duration,
+};
 
 #[derive(Subcommand)]
 pub enum ProfileCommands {
@@ -30,6 +39,36 @@ pub enum ProfileCommands {
     Dump,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    /// one JSON object per line
+    Jsonl,
+}
+
+impl From<ExportFormat> for InventoryFormat {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Csv => InventoryFormat::Csv,
+            ExportFormat::Jsonl => InventoryFormat::Jsonl,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum InventoryCommands {
+    /// Exports the inventory of every object below a folder
+    #[clap(aliases = &["e"])]
+    Export {
+        prefix: Option<String>,
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// output file, or '-' to write to stdout
+        #[arg(short, long)]
+        output: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Access to available profiles
@@ -42,14 +81,197 @@ pub enum Commands {
     List {
         path: Option<String>,
         #[arg(short, long)]
-        paginate: Option<usize>,
+        paginate: Option<NonZeroUsize>,
+        /// resumes the listing after the given key
+        #[arg(long, value_name = "KEY")]
+        start_after: Option<String>,
+        /// lists the content of every folder below PATH
+        #[arg(short, long, default_value_t = false)]
+        recursive: bool,
+        /// only lists the keys below PATH matching a glob, e.g. 'events/2024-*/*.parquet'
+        #[arg(long, conflicts_with = "regex")]
+        glob: Option<String>,
+        /// only lists the keys below PATH matching a regular expression
+        #[arg(long)]
+        regex: Option<String>,
+        /// sorts and prints the whole listing, or the entries of each page with --paginate
+        #[arg(long, value_enum)]
+        sort: Option<SortBy>,
+        /// reverses the sort order
+        #[arg(long, default_value_t = false, requires = "sort")]
+        reverse: bool,
+        /// shows directories before files
+        #[arg(long, default_value_t = false)]
+        dirs_first: bool,
+        /// comma separated columns to show
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = DEFAULT_COLUMNS)]
+        columns: Vec<Column>,
+    },
+    /// Prints the hierarchy below a folder with sizes
+    #[clap(aliases = &["t"])]
+    Tree {
+        path: Option<String>,
+        /// number of folder levels to descend into
+        #[arg(long)]
+        max_depth: Option<usize>,
+    },
+    /// Prints the metadata of an object or folder
+    #[clap(aliases = &["s", "st"])]
+    Stat {
+        path: String,
+        /// prints the metadata as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        /// prints the size in bytes
+        #[arg(long, default_value_t = false)]
+        raw: bool,
     },
+    /// Exports object inventories
+    #[clap(aliases = &["inv"])]
+    Inventory {
+        #[command(subcommand)]
+        command: InventoryCommands,
+    },
+    /// Finds the objects below a folder matching every given condition
+    #[clap(aliases = &["f"])]
+    Find {
+        path: Option<String>,
+        /// only objects larger than SIZE, e.g. 100MB or 1GiB
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        larger_than: Option<u64>,
+        /// only objects last modified longer than AGE ago, e.g. 30d or 12h
+        #[arg(long, value_name = "AGE", value_parser = parse_age)]
+        older_than: Option<Duration>,
+        /// only objects of this media type, or of a whole family as 'type/*'
+        #[arg(long)]
+        content_type: Option<String>,
+        /// prints the metadata of each object found as a line of JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+        /// deletes the objects found, once confirmed
+        #[arg(long, default_value_t = false, conflicts_with = "download_to")]
+        delete: bool,
+        /// deletes without asking for confirmation
+        #[arg(short, long, default_value_t = false, requires = "delete")]
+        yes: bool,
+        /// downloads the objects found into local directory DIR
+        #[arg(long, value_name = "DIR")]
+        download_to: Option<PathBuf>,
+    },
+    /// Sums the sizes of the objects below a folder and its sub-folders
+    Du {
+        path: Option<String>,
+        /// number of sub-folder levels summed separately
+        #[arg(short, long, default_value_t = 1)]
+        depth: usize,
+        /// only shows the N largest sub-folders
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+        /// prints sizes in bytes
+        #[arg(long, default_value_t = false)]
+        raw: bool,
+    },
+    /// Copies remote objects within the profile, or between profiles
+    #[clap(aliases = &["copy"])]
+    Cp {
+        /// remote path, prefixed with 'PROFILE:' to read another profile
+        src: String,
+        /// full remote key, or a folder when ending with '/', prefixed with
+        /// 'PROFILE:' to write another profile
+        dest: String,
+        /// copies every object below folder SRC into folder DEST
+        #[arg(short, long, default_value_t = false)]
+        recursive: bool,
+    },
+    /// Moves remote objects within the profile
+    #[clap(aliases = &["move"])]
+    Mv {
+        src: String,
+        /// full remote key, or a folder when ending with '/'
+        dest: String,
+        /// moves every object below folder SRC into folder DEST, resuming an
+        /// interrupted move of the same folders
+        #[arg(short, long, default_value_t = false)]
+        recursive: bool,
+        /// prints the planned moves without moving anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// Renames the objects below a folder whose key matches a regex
+    RenameMany {
+        prefix: String,
+        /// regex matched against full keys
+        #[arg(long)]
+        from: String,
+        /// replacement of the first match, '$1' or '${name}' standing for
+        /// capture groups
+        #[arg(long)]
+        to: String,
+        /// renames without asking for confirmation
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+        /// prints the planned renames without renaming anything
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+    },
+    /// Creates a folder marker, showing the folder even when empty
+    Mkdir { path: String },
+    /// Creates an empty object, or refreshes the modification time of an
+    /// existing one
+    Touch { path: String },
     #[clap(aliases = &["d", "del"])]
     Delete { path: String },
     #[clap(aliases = &["u", "up"])]
-    Upload { src: String, dest: String },
+    Upload {
+        /// local file or directory, or '-' to read from stdin
+        #[arg(required_unless_present_any = ["list_pending", "abort"])]
+        src: Option<String>,
+        /// full remote key, or a folder when ending with '/'
+        #[arg(required_unless_present_any = ["list_pending", "abort"])]
+        dest: Option<String>,
+        /// size in bytes of each uploaded part
+        #[arg(long)]
+        chunk_size: Option<NonZeroUsize>,
+        /// number of parts uploaded concurrently
+        #[arg(long)]
+        concurrent: Option<NonZeroUsize>,
+        /// uploads every file below directory SRC into folder DEST
+        #[arg(short, long, default_value_t = false, conflicts_with_all = ["resumable", "resume"])]
+        recursive: bool,
+        /// records progress so that an interrupted upload can be resumed
+        #[arg(long, default_value_t = false)]
+        resumable: bool,
+        /// continues the pending resumable upload of SRC to DEST
+        #[arg(long, default_value_t = false, conflicts_with = "resumable")]
+        resume: bool,
+        /// lists pending resumable uploads
+        #[arg(long, default_value_t = false, conflicts_with_all = ["src", "abort"])]
+        list_pending: bool,
+        /// aborts a pending resumable upload, discarding its uploaded parts
+        #[arg(long, value_name = "ID", conflicts_with = "src")]
+        abort: Option<String>,
+    },
     #[clap(aliases = &["dw", "down"])]
-    Download { src: String, dest: String },
+    Download {
+        src: String,
+        /// local file, or '-' to write to stdout
+        dest: String,
+        /// size in bytes of each range fetched in parallel
+        #[arg(long)]
+        part_size: Option<NonZeroU64>,
+        /// number of ranges fetched concurrently
+        #[arg(long)]
+        concurrent: Option<NonZeroUsize>,
+        /// objects smaller than this many bytes are fetched with a single read
+        #[arg(long)]
+        threshold: Option<u64>,
+        /// continue an interrupted download of the same object
+        #[arg(long, default_value_t = false)]
+        resume: bool,
+        /// downloads every object below folder SRC into directory DEST
+        #[arg(short, long, default_value_t = false)]
+        recursive: bool,
+    },
 }
 
 #[derive(Parser)]
@@ -59,6 +281,10 @@ pub struct Args {
     #[arg(short, long)]
     pub profile: Option<String>,
 
+    /// override the maximum number of operations in flight of the profile
+    #[arg(short, long, global = true)]
+    pub jobs: Option<usize>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
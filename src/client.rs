@@ -1,202 +1,3595 @@
 use crate::{
     buckets::{GCSConfig, S3Config},
     error,
+    multipart::{Multipart, MAX_PARTS, MIN_PART_SIZE},
+    opendal_builder,
+    scheduler::{Scheduler, TransferLimits},
 };
-use bytes::Buf;
-use futures::{stream, Future, Stream, StreamExt};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use bytes::{Buf, Bytes};
+use chrono::{DateTime, Utc};
+use futures::{future, stream, Future, Stream, StreamExt, TryStreamExt};
+use globset::{GlobBuilder, GlobMatcher};
+use md5::{Digest, Md5};
 pub use opendal::EntryMode;
-use opendal::{Entry, ErrorKind, Metakey, Operator};
-use std::{io::Read, path::Path, pin::Pin};
+use opendal::{ErrorKind, Lister, Metadata, Metakey, Operator};
+use regex::Regex;
+use regex_syntax::ast::{self, Ast};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::{self, Read, SeekFrom},
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::{
-    fs::File,
-    io::{AsyncReadExt, BufReader},
+    fs::{self, File, OpenOptions},
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt},
 };
 
 type Result<T> = std::result::Result<T, error::Client>;
 
-pub type StatEntry = (String, String, String, EntryMode);
+/// Metadata of a remote object or directory
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectMeta {
+    /// full path of the entry, directories end with '/'
+    pub path: String,
+    /// last segment of the path
+    pub name: String,
+    #[serde(with = "entry_mode")]
+    pub mode: EntryMode,
+    /// size in bytes, only known for files
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+    pub etag: Option<String>,
+    pub content_md5: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    /// version of the object, on backends with versioning enabled
+    pub version: Option<String>,
+    /// user defined metadata, without the backend specific key prefix
+    #[serde(default)]
+    pub user_metadata: HashMap<String, String>,
+}
+
+impl ObjectMeta {
+    fn new(path: &str, meta: &Metadata) -> Self {
+        let trimmed = path.strip_suffix('/').unwrap_or(path);
+        let name = match trimmed.rfind('/') {
+            Some(index) => &path[index + 1..],
+            None => path,
+        };
+
+        Self {
+            path: path.to_string(),
+            name: name.to_string(),
+            mode: meta.mode(),
+            content_length: meta.is_file().then(|| meta.content_length()),
+            content_type: meta.content_type().map(str::to_string),
+            last_modified: meta.last_modified(),
+            etag: meta.etag().map(str::to_string),
+            content_md5: meta.content_md5().map(str::to_string),
+            cache_control: meta.cache_control().map(str::to_string),
+            content_disposition: meta.content_disposition().map(str::to_string),
+            version: meta.version().map(str::to_string),
+            user_metadata: meta.user_metadata().cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode == EntryMode::DIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode == EntryMode::FILE
+    }
+
+    /// Path of the entry below folder `prefix`, or its full path when outside
+    pub fn relative_path(&self, prefix: &str) -> &str {
+        let prefix = prefix.trim_start_matches('/');
+
+        self.path
+            .trim_start_matches('/')
+            .strip_prefix(prefix)
+            .filter(|relative| !relative.is_empty())
+            .unwrap_or(&self.path)
+    }
+
+    /// Number of folders between `prefix` and the entry, its direct
+    /// children being at depth 1
+    pub fn depth(&self, prefix: &str) -> usize {
+        self.relative_path(prefix)
+            .trim_end_matches('/')
+            .matches('/')
+            .count()
+            + 1
+    }
+}
+
+/// (De)serializes [`EntryMode`] as "file", "dir" or "unknown"
+mod entry_mode {
+    use opendal::EntryMode;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(mode: &EntryMode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match *mode {
+            EntryMode::FILE => "file",
+            EntryMode::DIR => "dir",
+            EntryMode::Unknown => "unknown",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<EntryMode, D::Error> {
+        Ok(match String::deserialize(deserializer)?.as_str() {
+            "file" => EntryMode::FILE,
+            "dir" => EntryMode::DIR,
+            _ => EntryMode::Unknown,
+        })
+    }
+}
 
 const DEFAULT_LIST_LIMIT: usize = 10;
 
-#[derive(Clone)]
-pub struct Client {
-    inner: Operator,
+/// largest object copied server-side: S3 rejects a single CopyObject beyond
+/// 5 GiB, which are streamed instead
+const MAX_NATIVE_COPY_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Matches the full keys of listed entries, which have no leading '/'
+#[derive(Debug, Clone)]
+pub struct KeyFilter {
+    matcher: KeyMatcher,
+    /// literal start of the pattern, shared by every matching key
+    prefix: String,
 }
 
-impl Client {
-    pub async fn stat(&self, path: &str) -> Result<StatEntry> {
-        let meta = self
-            .inner
-            .stat(path)
-            .await
-            .map_err(|err| error::Client::ListMetadata(path.to_string(), err))?;
-        match meta.mode() {
-            EntryMode::Unknown => Err(error::Client::StatUnknownMode(path.to_string())),
-            EntryMode::FILE => Ok((
-                path.to_string(),
-                meta.content_type().unwrap_or_default().to_string(),
-                meta.content_length().to_string(),
-                EntryMode::FILE,
-            )),
-            EntryMode::DIR => Ok((
-                path.to_string(),
-                meta.content_type().unwrap_or_default().to_string(),
-                String::from(""),
-                EntryMode::DIR,
-            )),
-        }
-    }
-
-    async fn stat_entries(&self, path: &str, entries: Vec<Entry>) -> Vec<StatEntry> {
-        let mut list = vec![];
-
-        for entry in entries {
-            let meta = self
-                .inner
-                .stat(entry.path())
-                .await
-                .map_err(|err| error::Client::ListMetadata(path.to_string(), err));
+#[derive(Debug, Clone)]
+enum KeyMatcher {
+    Glob(GlobMatcher),
+    Regex(Regex),
+}
 
-            if let Ok(meta) = meta {
-                match meta.mode() {
-                    EntryMode::Unknown => continue,
-                    EntryMode::FILE => {
-                        list.push((
-                            entry.name().to_string(),
-                            meta.content_type().unwrap_or_default().to_string(),
-                            meta.content_length().to_string(),
-                            EntryMode::FILE,
-                        ));
-                    }
-                    EntryMode::DIR => {
-                        list.push((
-                            entry.name().to_string(),
-                            meta.content_type().unwrap_or_default().to_string(),
-                            String::from(""),
-                            EntryMode::DIR,
-                        ));
-                    }
-                }
-            } else {
-                println!("{:?}", meta.unwrap_err());
+impl KeyFilter {
+    /// Shell-like glob, where `*` does not cross folders, e.g.
+    /// `events/2024-*/*.parquet`
+    pub fn glob(pattern: &str) -> Result<Self> {
+        let pattern = pattern.trim_start_matches('/');
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|err| error::Client::ListInvalidFilter(pattern.to_string(), err.to_string()))?
+            .compile_matcher();
+        let prefix = pattern
+            .chars()
+            .take_while(|c| !"*?[{\\".contains(*c))
+            .collect();
+
+        Ok(Self {
+            matcher: KeyMatcher::Glob(matcher),
+            prefix,
+        })
+    }
+
+    /// Regular expression, only anchored ones (`^...`) without top-level
+    /// alternation narrow the listing
+    pub fn regex(pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern).map_err(|err| {
+            error::Client::ListInvalidFilter(pattern.to_string(), err.to_string())
+        })?;
+
+        // in `^a/b|c` the literal `a/b` only applies to one of the branches
+        let alternation = ast::parse::Parser::new()
+            .parse(pattern)
+            .map_or(true, |ast| matches!(ast, Ast::Alternation(_)));
+
+        let mut prefix = String::new();
+        if let Some(pattern) = pattern.strip_prefix('^').filter(|_| !alternation) {
+            let mut chars = pattern.chars().peekable();
+            while let Some(c) = chars.next_if(|c| !".^$*+?()[]{}|\\".contains(*c)) {
+                prefix.push(c);
+            }
+            // the last literal is optional or repeated
+            if chars.peek().is_some_and(|c| "*?{".contains(*c)) {
+                prefix.pop();
             }
         }
 
-        list
+        Ok(Self {
+            matcher: KeyMatcher::Regex(regex),
+            prefix,
+        })
     }
 
-    pub async fn list<'a>(
-        &'a self,
-        path: &'a str,
-        limit: Option<usize>,
-    ) -> Result<Pin<Box<dyn Stream<Item = impl Future<Output = Vec<StatEntry>> + '_> + '_>>> {
-        let should_paginate = limit.is_some();
-        let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
-
-        let client = self.inner.clone();
-        let entries = client
-            .list_with(path)
-            .metakey(Metakey::ContentLength)
-            .await
-            .map_err(|err| match err.kind() {
-                ErrorKind::NotADirectory => error::Client::ListNotDirectory(path.to_string()),
-                _ => error::Client::Unhandled(err),
-            })?;
+    pub fn is_match(&self, key: &str) -> bool {
+        match &self.matcher {
+            KeyMatcher::Glob(matcher) => matcher.is_match(key),
+            KeyMatcher::Regex(regex) => regex.is_match(key),
+        }
+    }
 
-        let stream = stream::iter(entries).chunks(limit);
+    /// Folder to list to find the matching entries of `path`: the deepest
+    /// folder of the literal prefix when it lies below `path`
+    fn folder<'a>(&'a self, path: &'a str) -> &'a str {
+        let folder = match self.prefix.rfind('/') {
+            Some(index) => &self.prefix[..=index],
+            None => return path,
+        };
 
-        if should_paginate {
-            Ok(stream.map(|chunk| self.stat_entries(path, chunk)).boxed())
+        if folder.starts_with(path.trim_start_matches('/')) {
+            folder
         } else {
-            Ok(stream
-                .take(1)
-                .map(|chunk| self.stat_entries(path, chunk))
-                .boxed())
+            path
         }
     }
+}
 
-    pub async fn download(&self, path: &str) -> Result<Vec<u8>> {
-        self.inner
-            .read(path)
-            .await
-            .map_err(error::Client::Download)
-            .and_then(|b| {
-                let mut buffer = vec![];
-                let mut reader = b.reader();
-                reader.read_to_end(&mut buffer).map_err(|err| {
-                    error::Client::Download(opendal::Error::new(
-                        opendal::ErrorKind::Unexpected,
-                        err.to_string(),
-                    ))
-                })?;
-                Ok(buffer)
-            })
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// number of entries of each page, greater than 0, a single page is
+    /// listed when unset
+    pub limit: Option<usize>,
+    /// resumes the listing after this key, recursive listings included
+    pub start_after: Option<String>,
+    /// lists the content of every folder below the listed one
+    pub recursive: bool,
+    /// only lists the entries whose key matches
+    pub filter: Option<KeyFilter>,
+}
+
+/// Conditions on the objects looked for by [`Client::find`], all of which
+/// must hold
+#[derive(Debug, Clone, Default)]
+pub struct FindOptions {
+    /// strictly more bytes than this
+    pub larger_than: Option<u64>,
+    /// last modified longer than this ago
+    pub older_than: Option<Duration>,
+    /// media type, or a whole family of them as `type/*`
+    pub content_type: Option<String>,
+}
+
+impl FindOptions {
+    pub fn matches(&self, meta: &ObjectMeta, now: DateTime<Utc>) -> bool {
+        let is_larger = match self.larger_than {
+            Some(size) => meta.content_length.is_some_and(|length| length > size),
+            None => true,
+        };
+        let is_older = match self.older_than {
+            Some(age) => meta.last_modified.is_some_and(|modified| {
+                (now - modified).to_std().is_ok_and(|elapsed| elapsed > age)
+            }),
+            None => true,
+        };
+        let has_content_type = match &self.content_type {
+            Some(pattern) => meta
+                .content_type
+                .as_deref()
+                .is_some_and(|content_type| media_type_matches(pattern, content_type)),
+            None => true,
+        };
+
+        is_larger && is_older && has_content_type
     }
+}
 
-    pub async fn upload(&self, src: &str, dest: &str, content_type: Option<&str>) -> Result<()> {
-        let filepath = Path::new(src);
-        let filename = filepath
-            .file_name()
-            .ok_or_else(|| error::Client::UploadInvalidFilePath(src.to_string()))?;
+/// Whether `content_type`, parameters aside, is `pattern` or of family `type/*`
+fn media_type_matches(pattern: &str, content_type: &str) -> bool {
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
 
-        let file = File::open(filepath)
-            .await
-            .map_err(error::Client::UploadFileNotFound)?;
-        let mut buffer: Vec<u8> = vec![];
-        BufReader::new(file)
-            .read_to_end(&mut buffer)
-            .await
-            .map_err(|err| error::Client::UploadLoad(src.to_string(), err))?;
+    match pattern.strip_suffix("/*") {
+        Some(family) => media_type
+            .split('/')
+            .next()
+            .is_some_and(|kind| kind.eq_ignore_ascii_case(family)),
+        None => media_type.eq_ignore_ascii_case(pattern),
+    }
+}
 
-        let dest = Path::new(dest).join(filename);
-        let dest = dest.to_str().unwrap();
-        match content_type {
-            None => {
-                self.inner
-                    .write(dest, buffer)
-                    .await
-                    .map_err(|err| error::Client::UploadWrite(dest.to_string(), err))?;
+/// Total size and number of the objects below a folder
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub prefix: String,
+    /// sum of the sizes in bytes of the objects
+    pub size: u64,
+    pub objects: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InventoryFormat {
+    Csv,
+    /// one JSON object per line
+    Jsonl,
+}
+
+/// A line of an inventory export
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InventoryRecord<'a> {
+    key: &'a str,
+    size: Option<u64>,
+    content_type: Option<&'a str>,
+    last_modified: Option<String>,
+    etag: Option<&'a str>,
+    /// not in opendal metadata, listed apart on S3 and GCS
+    storage_class: Option<String>,
+}
+
+impl<'a> From<&'a ObjectMeta> for InventoryRecord<'a> {
+    fn from(meta: &'a ObjectMeta) -> Self {
+        Self {
+            key: &meta.path,
+            size: meta.content_length,
+            content_type: meta.content_type.as_deref(),
+            last_modified: meta.last_modified.map(|modified| modified.to_rfc3339()),
+            etag: meta.etag.as_deref(),
+            storage_class: None,
+        }
+    }
+}
+
+const INVENTORY_CSV_HEADER: &str = "key,size,contentType,lastModified,etag,storageClass\n";
+
+impl InventoryRecord<'_> {
+    fn to_csv(&self) -> String {
+        let size = self.size.map(|size| size.to_string());
+        let fields = [
+            Some(self.key),
+            size.as_deref(),
+            self.content_type,
+            self.last_modified.as_deref(),
+            self.etag,
+            self.storage_class.as_deref(),
+        ];
+
+        let mut line = fields
+            .into_iter()
+            .map(|field| csv_field(field.unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join(",");
+        line.push('\n');
+
+        line
+    }
+}
+
+/// Quotes `field` when it holds a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Key and storage class of a listed object
+type ListedClass = (String, String);
+
+/// Storage class of object `key` from `classes`, listed in key order, the
+/// entry read past `key` being kept in `listed` for the next keys
+async fn storage_class<S>(
+    classes: &mut S,
+    listed: &mut Option<ListedClass>,
+    key: &str,
+) -> Result<Option<String>>
+where
+    S: Stream<Item = Result<ListedClass>> + Unpin,
+{
+    loop {
+        match listed {
+            Some((listed_key, class)) if listed_key == key => return Ok(Some(class.clone())),
+            Some((listed_key, _)) if listed_key.as_str() > key => return Ok(None),
+            _ => {}
+        }
+
+        match classes.try_next().await? {
+            Some(next) => *listed = Some(next),
+            None => return Ok(None),
+        }
+    }
+}
+
+/// A page of a listing
+#[derive(Debug, Default)]
+pub struct Page {
+    pub entries: Vec<ObjectMeta>,
+    /// key of the last listed entry, from which the listing can be resumed
+    pub cursor: Option<String>,
+}
+
+/// S3 rejects multipart parts smaller than 5MiB, except for the last one
+pub const DEFAULT_UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+pub const DEFAULT_UPLOAD_CONCURRENCY: usize = 1;
+
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    pub content_type: Option<String>,
+    /// size in bytes of each part sent to the backend
+    pub chunk_size: usize,
+    /// number of parts in flight at the same time
+    pub concurrent: usize,
+}
+
+impl UploadOptions {
+    /// Fails on a zero chunk size or concurrency, which would upload nothing
+    pub fn validate(&self) -> Result<()> {
+        if self.chunk_size == 0 {
+            return Err(error::Client::UploadInvalidOptions("chunk size"));
+        }
+        if self.concurrent == 0 {
+            return Err(error::Client::UploadInvalidOptions("concurrency"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for UploadOptions {
+    fn default() -> Self {
+        Self {
+            content_type: None,
+            chunk_size: DEFAULT_UPLOAD_CHUNK_SIZE,
+            concurrent: DEFAULT_UPLOAD_CONCURRENCY,
+        }
+    }
+}
+
+pub const DEFAULT_DOWNLOAD_PART_SIZE: u64 = 16 * 1024 * 1024;
+
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+
+pub const DEFAULT_DOWNLOAD_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// size in bytes of each range fetched from the backend
+    pub part_size: u64,
+    /// number of ranges fetched at the same time
+    pub concurrent: usize,
+    /// objects smaller than this many bytes are fetched with a single read
+    pub threshold: u64,
+    /// continue a previously interrupted download of the same object
+    pub resume: bool,
+}
+
+impl DownloadOptions {
+    /// Fails on a zero part size or concurrency
+    pub fn validate(&self) -> Result<()> {
+        if self.part_size == 0 {
+            return Err(error::Client::DownloadInvalidOptions("part size"));
+        }
+        if self.concurrent == 0 {
+            return Err(error::Client::DownloadInvalidOptions("concurrency"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            part_size: DEFAULT_DOWNLOAD_PART_SIZE,
+            concurrent: DEFAULT_DOWNLOAD_CONCURRENCY,
+            threshold: DEFAULT_DOWNLOAD_THRESHOLD,
+            resume: false,
+        }
+    }
+}
+
+const PARTIAL_DOWNLOAD_SUFFIX: &str = "rfpart";
+
+const PARTIAL_DOWNLOAD_STATE_SUFFIX: &str = "rfpart.json";
+
+/// Progress of a ranged download, stored next to its partial file
+#[derive(Debug, Deserialize, Serialize)]
+struct PartialDownload {
+    path: String,
+    etag: Option<String>,
+    size: u64,
+    part_size: u64,
+    /// start offsets of the ranges already written
+    completed: BTreeSet<u64>,
+}
+
+impl PartialDownload {
+    async fn load(path: &Path) -> Result<Option<Self>> {
+        let content = match fs::read(path).await {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(error::Client::DownloadState(
+                    path.display().to_string(),
+                    err,
+                ))
             }
-            Some(content_type) => {
-                self.inner
-                    .write_with(dest, buffer)
-                    .content_type(content_type)
-                    .await
-                    .map_err(|err| error::Client::UploadWrite(dest.to_string(), err))?;
+        };
+
+        serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|err| error::Client::DownloadState(path.display().to_string(), err.into()))
+    }
+
+    /// Writes the state next to `path` then renames it into place, so that a
+    /// crash never leaves a truncated state behind
+    async fn persist(&self, path: &Path) -> Result<()> {
+        let state_err = |err| error::Client::DownloadState(path.display().to_string(), err);
+        let content = serde_json::to_vec(self).map_err(|err| state_err(err.into()))?;
+
+        let tmp_path = with_suffix(path, "tmp");
+        fs::write(&tmp_path, content).await.map_err(state_err)?;
+        fs::rename(&tmp_path, path).await.map_err(state_err)
+    }
+}
+
+/// Appends `.{suffix}` to the file name of `path`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+
+    PathBuf::from(path)
+}
+
+/// Where a local file lands once uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadTarget {
+    /// full key of the uploaded object
+    Key(String),
+    /// folder the file is uploaded into, keeping its file name
+    Prefix(String),
+}
+
+impl UploadTarget {
+    /// Resolves the key of local file `src` uploaded to this target
+    pub fn key(&self, src: &str) -> Result<String> {
+        match self {
+            UploadTarget::Key(key) => Ok(key.clone()),
+            UploadTarget::Prefix(prefix) => {
+                let filename = Path::new(src)
+                    .file_name()
+                    .ok_or_else(|| error::Client::UploadInvalidFilePath(src.to_string()))?;
+
+                Ok(Path::new(prefix)
+                    .join(filename)
+                    .to_str()
+                    .unwrap()
+                    .to_string())
             }
         }
+    }
+}
 
-        Ok(())
+/// Outcome of a single file of a multi-file transfer
+#[derive(Debug)]
+pub struct Transfer {
+    pub src: String,
+    pub dest: String,
+    /// number of bytes transferred
+    pub result: Result<u64>,
+}
+
+/// Folder markers that could not be created above written keys, the writes
+/// themselves having succeeded
+pub type MarkerErrors = Vec<error::Client>;
+
+#[derive(Debug, Default)]
+pub struct TransferReport {
+    pub transfers: Vec<Transfer>,
+    pub marker_errors: MarkerErrors,
+}
+
+impl TransferReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = &Transfer> {
+        self.transfers
+            .iter()
+            .filter(|transfer| transfer.result.is_ok())
     }
 
-    pub async fn delete(&self, path: &str) -> Result<()> {
-        println!("{}", path);
-        self.inner
-            .remove_all(path)
-            .await
-            .map_err(|error| error::Client::Delete {
-                path: path.to_string(),
-                error,
-            })
+    pub fn failed(&self) -> impl Iterator<Item = &Transfer> {
+        self.transfers
+            .iter()
+            .filter(|transfer| transfer.result.is_err())
+    }
+
+    /// Total number of bytes of the successful transfers
+    pub fn bytes(&self) -> u64 {
+        self.transfers
+            .iter()
+            .filter_map(|transfer| transfer.result.as_ref().ok())
+            .sum()
     }
 }
 
-impl TryFrom<GCSConfig> for Client {
-    type Error = error::Client;
+/// Lists every file below local directory `root` alongside its size,
+/// following symlinks but visiting each directory once, so that a symlink to
+/// an ancestor cannot loop forever.
+///
+/// Only an unreadable `root` fails the walk: nested entries that cannot be
+/// read, e.g. dangling symlinks, are listed with their error instead.
+async fn walk_local(root: &Path) -> io::Result<Vec<(PathBuf, io::Result<u64>)>> {
+    let mut files = vec![];
+    let mut dirs = vec![root.to_path_buf()];
+    let mut visited = HashSet::new();
 
-    fn try_from(value: GCSConfig) -> std::result::Result<Self, Self::Error> {
-        Ok(Self {
-            inner: value.try_into().map_err(error::Client::Initialization)?,
-        })
+    while let Some(dir) = dirs.pop() {
+        match walk_local_dir(&dir, &mut visited, &mut dirs, &mut files).await {
+            Err(err) if dir != root => files.push((dir, Err(err))),
+            result => result?,
+        }
     }
+
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(files)
 }
 
-impl TryFrom<S3Config> for Client {
-    type Error = error::Client;
+/// Lists the entries of local directory `dir` for [`walk_local`], pushing
+/// subdirectories onto `dirs` and files onto `files`
+async fn walk_local_dir(
+    dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    dirs: &mut Vec<PathBuf>,
+    files: &mut Vec<(PathBuf, io::Result<u64>)>,
+) -> io::Result<()> {
+    if !visited.insert(fs::canonicalize(dir).await?) {
+        return Ok(());
+    }
 
-    fn try_from(value: S3Config) -> std::result::Result<Self, Self::Error> {
-        Ok(Self {
-            inner: value.try_into().map_err(error::Client::Initialization)?,
-        })
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        match fs::metadata(&path).await {
+            Ok(meta) if meta.is_dir() => dirs.push(path),
+            Ok(meta) if meta.is_file() => files.push((path, Ok(meta.len()))),
+            Ok(_) => {}
+            Err(err) => files.push((path, Err(err))),
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps remote relative path `path` below local directory `root`, refusing
+/// paths that would escape it
+fn local_path(root: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let is_nested = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+
+    (is_nested && path.components().next().is_some()).then(|| root.join(path))
+}
+
+/// Remote folder `path` as a key prefix, empty for the root
+fn folder_prefix(path: &str) -> String {
+    match path {
+        "" | "/" => String::new(),
+        path if path.ends_with('/') => path.to_string(),
+        path => format!("{path}/"),
+    }
+}
+
+/// Streams object `src` of `from` into `dest` of `to` through this process,
+/// keeping the metadata described by `meta`, and returns its size and the
+/// MD5 digest of the bytes streamed.
+///
+/// Backends silently drop the metadata they cannot write.
+async fn stream_object(
+    from: &Operator,
+    src: &str,
+    to: &Operator,
+    dest: &str,
+    meta: &Metadata,
+) -> Result<(u64, Vec<u8>)> {
+    let copy_error = |err| error::Client::Copy(src.to_string(), dest.to_string(), err);
+
+    let reader = from.reader(src).await.map_err(copy_error)?;
+    let mut writer = opendal_builder!(
+        to.writer_with(dest).chunk(DEFAULT_UPLOAD_CHUNK_SIZE),
+        meta.content_type() => content_type,
+        meta.cache_control() => cache_control,
+        meta.content_disposition() => content_disposition,
+        meta.user_metadata().cloned() => user_metadata
+    )
+    .await
+    .map_err(copy_error)?;
+
+    let size = meta.content_length();
+    let mut hasher = Md5::new();
+    for range in split_ranges(size, DEFAULT_UPLOAD_CHUNK_SIZE as u64) {
+        let written = match reader.read(range).await {
+            Ok(buffer) => {
+                for chunk in buffer.clone() {
+                    hasher.update(chunk);
+                }
+                writer.write(buffer).await
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = written {
+            let _ = writer.abort().await;
+            return Err(copy_error(err));
+        }
+    }
+
+    writer.close().await.map_err(copy_error)?;
+
+    Ok((size, hasher.finalize().to_vec()))
+}
+
+/// Whether the MD5 checksum exposed by `meta`, if any, is `digest`.
+///
+/// Etags are not compared: they are only an MD5 for some objects of some
+/// backends.
+fn md5_matches(meta: &Metadata, digest: &[u8]) -> bool {
+    match meta.content_md5() {
+        Some(md5) => md5 == BASE64_STANDARD.encode(digest),
+        None => true,
+    }
+}
+
+/// Appends the components of local relative path `path` to remote folder `prefix`
+fn join_key(prefix: &str, path: &Path) -> String {
+    let path = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match prefix {
+        "" => path,
+        prefix if prefix.ends_with('/') => format!("{prefix}{path}"),
+        prefix => format!("{prefix}/{path}"),
+    }
+}
+
+/// A crash-safe upload of a local file.
+///
+/// The file is sent as a multipart upload of the backend, whose id and
+/// uploaded parts are recorded so that another process can continue it. Parts
+/// stay out of listings until the upload is completed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSession {
+    pub id: String,
+    pub profile: String,
+    pub src: PathBuf,
+    pub dest: String,
+    pub size: u64,
+    /// last modification time of `src` when the session started
+    pub modified: SystemTime,
+    pub part_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// id of the multipart upload of the backend
+    pub upload_id: String,
+    /// ETag of each part already uploaded, by index
+    pub completed: BTreeMap<u64, String>,
+}
+
+/// Index and ETag of a part of an [`UploadSession`]
+pub type UploadedPart = (u64, String);
+
+impl UploadSession {
+    /// Whether this session uploads local file `src` to `target`
+    pub async fn matches(&self, profile: &str, src: &str, target: &UploadTarget) -> bool {
+        let Ok(key) = target.key(src) else {
+            return false;
+        };
+
+        self.profile == profile
+            && self.dest == key
+            && fs::canonicalize(src).await.is_ok_and(|src| src == self.src)
+    }
+
+    /// Fails when the local file changed size or modification time since the
+    /// session started
+    pub async fn check_source(&self) -> Result<()> {
+        let meta = fs::metadata(&self.src)
+            .await
+            .map_err(error::Client::UploadFileNotFound)?;
+        let modified = meta
+            .modified()
+            .map_err(|err| error::Client::UploadLoad(self.src.display().to_string(), err))?;
+
+        if meta.len() != self.size || modified != self.modified {
+            return Err(error::Client::UploadResumeMismatch(
+                self.src.display().to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn parts(&self) -> u64 {
+        self.size.div_ceil(self.part_size).max(1)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed.len() as u64 == self.parts()
+    }
+
+    /// Offset and length of part `index` in the local file
+    fn part_range(&self, index: u64) -> (u64, u64) {
+        let offset = index * self.part_size;
+
+        (offset, self.part_size.min(self.size - offset))
+    }
+}
+
+/// Identifier of a new resumable session, unique to this machine
+fn session_id() -> String {
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    format!("{id:x}")
+}
+
+/// A resumable move of every object below a remote folder, or bulk rename
+/// of the objects below it.
+///
+/// Every object is copied and verified before any source is deleted, so that
+/// an interrupted move never loses an object, and progress is recorded per
+/// object so that a resumed move skips the work already done.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveSession {
+    pub id: String,
+    pub profile: String,
+    /// source folder, as a key prefix
+    pub src: String,
+    /// destination folder, as a key prefix, or replacement template of a bulk
+    /// rename
+    pub dest: String,
+    /// key pattern of a bulk rename
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// destination key of each object of a bulk rename, by source key
+    #[serde(default)]
+    pub renames: BTreeMap<String, String>,
+    /// size of each object to move, by source key
+    pub objects: BTreeMap<String, u64>,
+    /// source keys copied and verified at their destination
+    pub copied: BTreeSet<String>,
+    /// source keys deleted once copied
+    pub deleted: BTreeSet<String>,
+}
+
+impl MoveSession {
+    /// Whether this session moves folder `src` to folder `dest`
+    pub fn matches(&self, profile: &str, src: &str, dest: &str) -> bool {
+        self.profile == profile
+            && self.pattern.is_none()
+            && self.src == folder_prefix(src)
+            && self.dest == folder_prefix(dest)
+    }
+
+    /// Whether this session renames the keys below folder `prefix` matching
+    /// `from` with template `to`
+    pub fn matches_rename(&self, profile: &str, prefix: &str, from: &str, to: &str) -> bool {
+        self.profile == profile
+            && self.pattern.as_deref() == Some(from)
+            && self.src == folder_prefix(prefix)
+            && self.dest == to
+    }
+
+    /// Destination key of source key `key`
+    pub fn dest_key(&self, key: &str) -> String {
+        if let Some(dest) = self.renames.get(key) {
+            return dest.clone();
+        }
+
+        let relative = key.strip_prefix(self.src.as_str()).unwrap_or(key);
+
+        format!("{}{relative}", self.dest)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.deleted.len() == self.objects.len()
+    }
+}
+
+/// A destination key of a bulk rename claimed by several objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameCollision {
+    pub dest: String,
+    /// source keys renamed to `dest`, and `dest` itself when it exists
+    pub srcs: Vec<String>,
+}
+
+/// Splits `size` bytes into consecutive ranges of at most `part_size` bytes
+fn split_ranges(size: u64, part_size: u64) -> Vec<Range<u64>> {
+    let part_size = part_size.max(1);
+
+    (0..size)
+        .step_by(part_size as usize)
+        .map(|start| start..(start + part_size).min(size))
+        .collect()
+}
+
+#[derive(Clone)]
+pub struct Client {
+    inner: Operator,
+    /// XML API of the backend, which resumable uploads and storage classes
+    /// require
+    multipart: Option<Multipart>,
+    scheduler: Scheduler,
+    /// whether uploads create a marker for each folder above their key
+    directory_markers: bool,
+}
+
+impl Client {
+    /// Replaces the limits applied to multi-file operations
+    pub fn with_limits(self, limits: TransferLimits) -> Self {
+        Self {
+            scheduler: Scheduler::new(limits),
+            ..self
+        }
+    }
+
+    pub fn limits(&self) -> TransferLimits {
+        self.scheduler.limits()
+    }
+
+    /// Makes uploads create a zero-byte `folder/` marker for each folder
+    /// above the keys they write, as GCS consoles do.
+    ///
+    /// Backends with native folders are first checked for each marker, at
+    /// the cost of an extra request per folder. Others, S3 and GCS among
+    /// them, cannot tell a marker from a prefix: each upload rewrites every
+    /// marker above it.
+    pub fn with_directory_markers(self, directory_markers: bool) -> Self {
+        Self {
+            directory_markers,
+            ..self
+        }
+    }
+
+    /// Metadata of object or folder `path`.
+    ///
+    /// Folders are reported alike whether they have a marker or only exist
+    /// as the prefix of the objects below them.
+    pub async fn stat(&self, path: &str) -> Result<ObjectMeta> {
+        let meta = match self.inner.stat(path).await {
+            Ok(meta) => meta,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let folder = folder_prefix(path);
+                if !self.has_entries(&folder).await? {
+                    return Err(error::Client::StatNotFound(path.to_string()));
+                }
+
+                return Ok(ObjectMeta::new(&folder, &Metadata::new(EntryMode::DIR)));
+            }
+            Err(err) => return Err(error::Client::ListMetadata(path.to_string(), err)),
+        };
+        match meta.mode() {
+            EntryMode::Unknown => Err(error::Client::StatUnknownMode(path.to_string())),
+            _ => Ok(ObjectMeta::new(path, &meta)),
+        }
+    }
+
+    /// Whether anything, a marker or an object, exists below folder `prefix`
+    async fn has_entries(&self, prefix: &str) -> Result<bool> {
+        let mut lister = self
+            .inner
+            .lister_with(prefix)
+            .recursive(true)
+            .limit(1)
+            .await
+            .map_err(|err| error::Client::ListMetadata(prefix.to_string(), err))?;
+
+        match lister.next().await {
+            Some(entry) => entry
+                .map(|_| true)
+                .map_err(|err| error::Client::ListMetadata(prefix.to_string(), err)),
+            None => Ok(false),
+        }
+    }
+
+    /// Creates a marker for folder `path`, making it show in listings even
+    /// when empty
+    pub async fn mkdir(&self, path: &str) -> Result<()> {
+        let folder = folder_prefix(path);
+        if folder.is_empty() {
+            return Ok(());
+        }
+
+        self.inner
+            .create_dir(&folder)
+            .await
+            .map_err(|err| error::Client::Mkdir(folder.clone(), err))
+    }
+
+    /// Creates the markers of every folder above `keys` that are not there
+    /// yet, once each, when the client creates directory markers.
+    ///
+    /// A failing marker does not stop the others: the errors are returned.
+    async fn create_markers<'a, I>(&self, keys: I) -> MarkerErrors
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        if !self.directory_markers {
+            return vec![];
+        }
+
+        let folders = keys
+            .into_iter()
+            .flat_map(|key| key.match_indices('/').map(|(index, _)| &key[..=index]))
+            .filter(|folder| *folder != "/")
+            .collect::<BTreeSet<_>>();
+        // without native folders, a folder "exists" as soon as an object is
+        // below it: only backends with native folders can tell a marker apart
+        let check = self.inner.info().native_capability().create_dir;
+        let mut errors = vec![];
+        for folder in folders {
+            if check && self.inner.is_exist(folder).await.unwrap_or(false) {
+                continue;
+            }
+
+            if let Err(err) = self.mkdir(folder).await {
+                errors.push(err);
+            }
+        }
+
+        errors
+    }
+
+    /// Creates an empty object at `path`, or rewrites the existing object in
+    /// place, keeping its content and metadata, so that its last modification
+    /// time is now.
+    ///
+    /// Backends with a native copy rewrite it server-side. Others, or those
+    /// refusing self-copies without a metadata change such as S3, stream it
+    /// through this process.
+    ///
+    /// Returns the folder markers that could not be created above `path`.
+    pub async fn touch(&self, path: &str) -> Result<MarkerErrors> {
+        if path.ends_with('/') {
+            return Err(error::Client::NotAnObject(path.to_string()));
+        }
+
+        let touch_err = |err| error::Client::Touch(path.to_string(), err);
+        match self.inner.stat(path).await {
+            Ok(meta) if meta.is_file() => {
+                let _permit = self.scheduler.acquire(meta.content_length()).await;
+                let copied = self.inner.info().full_capability().copy
+                    && self.inner.copy(path, path).await.is_ok();
+
+                if !copied {
+                    stream_object(&self.inner, path, &self.inner, path, &meta)
+                        .await
+                        .map_err(|err| match err {
+                            error::Client::Copy(_, _, err) => touch_err(err),
+                            err => err,
+                        })?;
+                }
+            }
+            Ok(_) => return Err(error::Client::NotAnObject(path.to_string())),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let _permit = self.scheduler.acquire(0).await;
+                self.inner
+                    .write(path, Vec::<u8>::new())
+                    .await
+                    .map_err(touch_err)?;
+            }
+            Err(err) => return Err(touch_err(err)),
+        }
+
+        Ok(self.create_markers([path]).await)
+    }
+
+    /// Opens a lister on folder `path` with the metadata shown by listings
+    async fn lister(
+        &self,
+        path: &str,
+        limit: Option<usize>,
+        start_after: Option<&str>,
+        recursive: bool,
+    ) -> Result<Lister> {
+        let capability = self.inner.info().full_capability();
+
+        opendal_builder!(
+            self.inner
+                .lister_with(path)
+                .metakey(
+                    Metakey::Mode
+                        | Metakey::ContentType
+                        | Metakey::ContentLength
+                        | Metakey::LastModified
+                        | Metakey::Etag,
+                )
+                .concurrent(self.limits().jobs)
+                .recursive(recursive),
+            limit => limit,
+            start_after.filter(|_| capability.list_with_start_after) => start_after
+        )
+        .await
+        .map_err(|err| match err.kind() {
+            ErrorKind::NotADirectory => error::Client::ListNotDirectory(path.to_string()),
+            _ => error::Client::Unhandled(err),
+        })
+    }
+
+    /// Maps the entries of a lister on `folder` to [`ObjectMeta`], skipping
+    /// the folder itself and entries of unknown mode
+    fn list_entries(
+        folder: String,
+        lister: Lister,
+    ) -> impl Stream<Item = Result<ObjectMeta>> + Send {
+        lister
+            .map_err(move |err| error::Client::ListMetadata(folder.clone(), err))
+            .try_filter_map(move |entry| {
+                future::ready(Ok(match entry.metadata().mode() {
+                    EntryMode::Unknown => None,
+                    _ => Some(ObjectMeta::new(entry.path(), entry.metadata())),
+                }))
+            })
+    }
+
+    pub async fn list<'a>(
+        &'a self,
+        path: &'a str,
+        limit: Option<usize>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Page>> + Send + 'a>>> {
+        let options = ListOptions {
+            limit,
+            ..Default::default()
+        };
+
+        self.list_with(path, &options).await
+    }
+
+    /// Lists `path` one page of `options.limit` entries at a time.
+    ///
+    /// Entries are pulled lazily from the backend, so that a page is only
+    /// fetched once the stream is polled for it. Without a limit only the
+    /// first page is returned.
+    ///
+    /// Metadata comes with the listing itself. Backends whose listing lacks
+    /// some of it are sent one stat per entry, at most as many at a time as
+    /// the client [`TransferLimits`] allow jobs.
+    ///
+    /// A [`KeyFilter`] lists every entry below the literal folder of its
+    /// pattern, rather than the whole of `path`, and keeps the matching ones.
+    pub async fn list_with<'a>(
+        &'a self,
+        path: &'a str,
+        options: &ListOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Page>> + Send + 'a>>> {
+        if options.limit == Some(0) {
+            return Err(error::Client::ListInvalidLimit);
+        }
+
+        let should_paginate = options.limit.is_some();
+        let limit = options.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        let start_after = options.start_after.as_deref();
+
+        let entries = match &options.filter {
+            Some(filter) => {
+                let folder = filter.folder(path);
+                let lister = self.lister(folder, Some(limit), start_after, true).await?;
+
+                Self::list_entries(folder.to_string(), lister).boxed()
+            }
+            None if options.recursive => self.walk_after(path, None, start_after).await?,
+            None => {
+                let lister = self.lister(path, Some(limit), start_after, false).await?;
+
+                Self::list_entries(path.to_string(), lister).boxed()
+            }
+        };
+
+        // some backends start listing at the given key rather than after it,
+        // others cannot start from a key at all
+        let start_after = options.start_after.clone();
+        let filter = options.filter.clone();
+        let entries = entries.try_filter(move |entry| {
+            let is_listed = entry.path.as_str() != path.trim_start_matches('/')
+                && match start_after.as_deref() {
+                    Some(start_after) => entry.path.as_str() > start_after,
+                    None => true,
+                }
+                && match &filter {
+                    Some(filter) => filter.is_match(&entry.path),
+                    None => true,
+                };
+
+            future::ready(is_listed)
+        });
+
+        let pages = entries.chunks(limit).map(|chunk| {
+            let entries = chunk.into_iter().collect::<Result<Vec<_>>>()?;
+            let cursor = entries.last().map(|entry| entry.path.clone());
+
+            Ok(Page { entries, cursor })
+        });
+
+        if should_paginate {
+            Ok(pages.boxed())
+        } else {
+            Ok(pages.take(1).boxed())
+        }
+    }
+
+    /// Streams the objects below folder `path` matching every condition of
+    /// `options`, from a recursive listing of `path`
+    pub async fn find<'a>(
+        &'a self,
+        path: &'a str,
+        options: &FindOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ObjectMeta>> + Send + 'a>>> {
+        let folder = folder_prefix(path);
+        let lister = self.lister(&folder, None, None, true).await?;
+        let options = options.clone();
+        let now = Utc::now();
+
+        let objects = Self::list_entries(folder, lister)
+            .try_filter(move |entry| future::ready(entry.is_file() && options.matches(entry, now)));
+
+        Ok(objects.boxed())
+    }
+
+    /// Writes the inventory of every object below folder `prefix` to
+    /// `writer`, line by line as the recursive listing goes, and returns the
+    /// number of objects.
+    ///
+    /// Storage classes come from a second listing, along the first one, on
+    /// backends with an XML API, and are left empty on the others.
+    pub async fn export_inventory<W>(
+        &self,
+        prefix: &str,
+        format: InventoryFormat,
+        writer: &mut W,
+    ) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let folder = folder_prefix(prefix);
+        let lister = self.lister(&folder, None, None, true).await?;
+        let mut objects = Self::list_entries(folder.clone(), lister)
+            .try_filter(|entry| future::ready(entry.is_file()));
+        let mut classes = self.storage_classes(&folder);
+        let mut listed_class = None;
+
+        if format == InventoryFormat::Csv {
+            writer
+                .write_all(INVENTORY_CSV_HEADER.as_bytes())
+                .await
+                .map_err(error::Client::InventoryWrite)?;
+        }
+
+        let mut count = 0;
+        while let Some(object) = objects.try_next().await? {
+            let record = InventoryRecord {
+                storage_class: storage_class(&mut classes, &mut listed_class, &object.path).await?,
+                ..InventoryRecord::from(&object)
+            };
+            let line = match format {
+                InventoryFormat::Csv => record.to_csv(),
+                InventoryFormat::Jsonl => {
+                    let mut line = serde_json::to_string(&record)
+                        .map_err(|err| error::Client::InventoryWrite(err.into()))?;
+                    line.push('\n');
+                    line
+                }
+            };
+
+            writer
+                .write_all(line.as_bytes())
+                .await
+                .map_err(error::Client::InventoryWrite)?;
+            count += 1;
+        }
+
+        writer
+            .flush()
+            .await
+            .map_err(error::Client::InventoryWrite)?;
+
+        Ok(count)
+    }
+
+    /// Streams the keys and storage classes of the objects below folder
+    /// `prefix` in key order, from the XML API of the backends exposing it
+    fn storage_classes<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<ListedClass>> + Send + 'a>> {
+        let Some(multipart) = &self.multipart else {
+            return stream::empty().boxed();
+        };
+
+        // the token of the next page, `None` once the last one is listed
+        let pages = stream::try_unfold(
+            Some(None),
+            move |token: Option<Option<String>>| async move {
+                let Some(token) = token else {
+                    return Ok(None);
+                };
+                let (classes, next) = multipart
+                    .storage_classes(prefix, token.as_deref())
+                    .await
+                    .map_err(|err| error::Client::ListMetadata(prefix.to_string(), err))?;
+
+                Ok(Some((
+                    stream::iter(classes.into_iter().map(Ok)),
+                    next.map(Some),
+                )))
+            },
+        );
+
+        pages.try_flatten().fuse().boxed()
+    }
+
+    /// Sums the sizes and counts of the objects below folder `path`, for
+    /// `path` itself and for every folder down to `depth` levels below it.
+    ///
+    /// Usages are sorted from the largest, `path` being the first one, as a
+    /// folder ending with `/`.
+    pub async fn disk_usage(&self, path: &str, depth: usize) -> Result<Vec<Usage>> {
+        let prefix = folder_prefix(path.trim_start_matches('/'));
+        let root = match prefix.as_str() {
+            "" => String::from("/"),
+            prefix => prefix.to_string(),
+        };
+        let mut lister = self
+            .inner
+            .lister_with(&prefix)
+            .recursive(true)
+            .metakey(Metakey::Mode | Metakey::ContentLength)
+            .await
+            .map_err(|err| match err.kind() {
+                ErrorKind::NotADirectory => error::Client::ListNotDirectory(path.to_string()),
+                _ => error::Client::Unhandled(err),
+            })?;
+
+        let mut usages = HashMap::from([(root.clone(), Usage::default())]);
+        while let Some(entry) = lister
+            .try_next()
+            .await
+            .map_err(|err| error::Client::ListMetadata(path.to_string(), err))?
+        {
+            if !entry.metadata().is_file() {
+                continue;
+            }
+
+            let size = entry.metadata().content_length();
+            let relative = entry
+                .path()
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(entry.path());
+            let folders = relative
+                .match_indices('/')
+                .take(depth)
+                .map(|(index, _)| format!("{prefix}{}", &relative[..=index]));
+
+            for folder in std::iter::once(root.clone()).chain(folders) {
+                let usage = usages.entry(folder).or_default();
+                usage.size += size;
+                usage.objects += 1;
+            }
+        }
+
+        let mut usages = usages
+            .into_iter()
+            .map(|(prefix, usage)| Usage { prefix, ..usage })
+            .collect::<Vec<_>>();
+        usages.sort_by(|a, b| {
+            (b.prefix == root)
+                .cmp(&(a.prefix == root))
+                .then_with(|| b.size.cmp(&a.size))
+                .then_with(|| a.prefix.cmp(&b.prefix))
+        });
+
+        Ok(usages)
+    }
+
+    /// Walks the entries below folder `prefix` depth-first, each folder being
+    /// yielded right before its content.
+    ///
+    /// Folders deeper than `max_depth` are yielded without being listed.
+    pub async fn walk<'a>(
+        &'a self,
+        prefix: &'a str,
+        max_depth: Option<usize>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ObjectMeta>> + Send + 'a>>> {
+        self.walk_after(prefix, max_depth, None).await
+    }
+
+    /// Walks like [`Client::walk`], only yielding the entries after key
+    /// `start_after` and skipping the folders that hold none of them
+    async fn walk_after<'a>(
+        &'a self,
+        prefix: &'a str,
+        max_depth: Option<usize>,
+        start_after: Option<&str>,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ObjectMeta>> + Send + 'a>>> {
+        let start_after = start_after.map(str::to_string);
+        // listers of the folders being walked, with their path and depth
+        let stack = vec![(
+            prefix.to_string(),
+            1,
+            self.lister(prefix, None, start_after.as_deref(), false)
+                .await?,
+        )];
+
+        let state = (stack, start_after);
+        let entries = stream::try_unfold(state, move |(mut stack, start_after)| async move {
+            while let Some((folder, depth, lister)) = stack.last_mut() {
+                let depth = *depth;
+                let entry = match lister.next().await {
+                    Some(entry) => {
+                        entry.map_err(|err| error::Client::ListMetadata(folder.clone(), err))?
+                    }
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                };
+
+                // some backends list a folder along with its content
+                if entry.path() == folder.as_str() {
+                    continue;
+                }
+
+                let meta = ObjectMeta::new(entry.path(), entry.metadata());
+                let is_listed = match start_after.as_deref() {
+                    Some(start_after) => meta.path.as_str() > start_after,
+                    None => true,
+                };
+                // a folder sorting before `start_after` only holds entries
+                // after it when it is one of its ancestors
+                let is_walked = is_listed
+                    || start_after
+                        .as_deref()
+                        .is_some_and(|start_after| start_after.starts_with(&meta.path));
+
+                match meta.mode {
+                    EntryMode::Unknown => continue,
+                    EntryMode::DIR if is_walked && depth < max_depth.unwrap_or(usize::MAX) => {
+                        let lister = self
+                            .lister(&meta.path, None, start_after.as_deref(), false)
+                            .await?;
+                        stack.push((meta.path.clone(), depth + 1, lister));
+                    }
+                    _ => {}
+                }
+
+                if is_listed {
+                    return Ok(Some((meta, (stack, start_after))));
+                }
+            }
+
+            Ok(None)
+        });
+
+        Ok(entries.boxed())
+    }
+
+    pub async fn download(&self, path: &str) -> Result<Vec<u8>> {
+        let _permit = self.scheduler.acquire(0).await;
+        self.inner
+            .read(path)
+            .await
+            .map_err(error::Client::Download)
+            .and_then(|b| {
+                let mut buffer = vec![];
+                let mut reader = b.reader();
+                reader.read_to_end(&mut buffer).map_err(|err| {
+                    error::Client::Download(opendal::Error::new(
+                        opendal::ErrorKind::Unexpected,
+                        err.to_string(),
+                    ))
+                })?;
+                Ok(buffer)
+            })
+    }
+
+    /// Streams the content of `path` into `writer` chunk by chunk,
+    /// without holding the whole object in memory.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn download_to<W>(&self, path: &str, writer: &mut W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let _permit = self.scheduler.acquire(0).await;
+        self.stream_to(path, writer).await
+    }
+
+    /// Same as [`Client::download_to`], within a permit held by the caller
+    async fn stream_to<W>(&self, path: &str, writer: &mut W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut stream = self
+            .inner
+            .reader(path)
+            .await
+            .map_err(error::Client::Download)?
+            .into_bytes_stream(..)
+            .await
+            .map_err(error::Client::Download)?;
+
+        let mut written = 0;
+        while let Some(bytes) = stream
+            .try_next()
+            .await
+            .map_err(|err| error::Client::DownloadStream(path.to_string(), err))?
+        {
+            writer
+                .write_all(&bytes)
+                .await
+                .map_err(|err| error::Client::DownloadWrite(path.to_string(), err))?;
+            written += bytes.len() as u64;
+        }
+
+        writer
+            .flush()
+            .await
+            .map_err(|err| error::Client::DownloadWrite(path.to_string(), err))?;
+
+        Ok(written)
+    }
+
+    /// Streams the content of `path` into the local file `dest`,
+    /// creating or truncating it.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn download_file<P>(&self, path: &str, dest: P) -> Result<u64>
+    where
+        P: AsRef<Path>,
+    {
+        self.download_file_with(path, dest, &DownloadOptions::default())
+            .await
+    }
+
+    /// Downloads `path` into the local file `dest`, creating or truncating it.
+    ///
+    /// Objects of at least `options.threshold` bytes are split into ranges of
+    /// `options.part_size` bytes, fetched `options.concurrent` at a time and
+    /// written at their offset in a `<dest>.rfpart` file, which is renamed to
+    /// `dest` once complete. Progress is recorded in a `<dest>.rfpart.json`
+    /// sidecar so that a later call with `options.resume` only fetches the
+    /// missing ranges. Smaller objects are streamed with a single read, unless
+    /// resuming: they are then fetched by ranges as well.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn download_file_with<P>(
+        &self,
+        path: &str,
+        dest: P,
+        options: &DownloadOptions,
+    ) -> Result<u64>
+    where
+        P: AsRef<Path>,
+    {
+        options.validate()?;
+
+        let dest = dest.as_ref();
+        let meta = self
+            .inner
+            .stat(path)
+            .await
+            .map_err(error::Client::Download)?;
+        let size = meta.content_length();
+
+        if size < options.threshold && !options.resume {
+            let mut file = File::create(dest)
+                .await
+                .map_err(|err| error::Client::DownloadWrite(dest.display().to_string(), err))?;
+
+            let _permit = self.scheduler.acquire(size).await;
+            return self.stream_to(path, &mut file).await;
+        }
+
+        let partial_path = with_suffix(dest, PARTIAL_DOWNLOAD_SUFFIX);
+        let state_path = with_suffix(dest, PARTIAL_DOWNLOAD_STATE_SUFFIX);
+        let previous = if options.resume {
+            PartialDownload::load(&state_path).await?
+        } else {
+            None
+        };
+
+        let partial_exists = fs::try_exists(&partial_path).await.unwrap_or(false);
+
+        let mut state = match previous {
+            Some(state) if state.path == path && partial_exists => {
+                if state.etag.as_deref() != meta.etag() || state.size != size {
+                    return Err(error::Client::DownloadResumeMismatch(path.to_string()));
+                }
+
+                state
+            }
+            _ => {
+                let file = File::create(&partial_path).await.map_err(|err| {
+                    error::Client::DownloadWrite(partial_path.display().to_string(), err)
+                })?;
+                file.set_len(size).await.map_err(|err| {
+                    error::Client::DownloadWrite(partial_path.display().to_string(), err)
+                })?;
+
+                let state = PartialDownload {
+                    path: path.to_string(),
+                    etag: meta.etag().map(String::from),
+                    size,
+                    part_size: options.part_size,
+                    completed: BTreeSet::new(),
+                };
+                state.persist(&state_path).await?;
+
+                state
+            }
+        };
+
+        let missing = split_ranges(size, state.part_size)
+            .into_iter()
+            .filter(|range| !state.completed.contains(&range.start))
+            .collect::<Vec<_>>();
+        let mut ranges = stream::iter(missing)
+            .map(|range| self.download_range(path, &partial_path, range))
+            .buffer_unordered(options.concurrent.max(1));
+
+        while let Some(range) = ranges.try_next().await? {
+            state.completed.insert(range.start);
+            state.persist(&state_path).await?;
+        }
+
+        fs::rename(&partial_path, dest)
+            .await
+            .map_err(|err| error::Client::DownloadWrite(dest.display().to_string(), err))?;
+        let _ = fs::remove_file(&state_path).await;
+
+        Ok(size)
+    }
+
+    /// Downloads every object below remote folder `src` into local directory
+    /// `dest`, recreating the folder tree, within the client
+    /// [`TransferLimits`].
+    ///
+    /// Directory markers are skipped. A failing object does not abort the
+    /// others: each outcome is reported in the returned [`TransferReport`].
+    pub async fn download_dir(
+        &self,
+        src: &str,
+        dest: &Path,
+        options: &DownloadOptions,
+    ) -> Result<TransferReport> {
+        let prefix = folder_prefix(src);
+        let objects = self.list_objects(&prefix).await?;
+
+        Ok(self.download_many(&prefix, objects, dest, options).await)
+    }
+
+    /// Paths and sizes of every object below key prefix `prefix`
+    async fn list_objects(&self, prefix: &str) -> Result<Vec<(String, u64)>> {
+        let entries = self
+            .inner
+            .list_with(prefix)
+            .recursive(true)
+            .metakey(Metakey::Mode | Metakey::ContentLength)
+            .await
+            .map_err(|err| match err.kind() {
+                ErrorKind::NotADirectory => error::Client::ListNotDirectory(prefix.to_string()),
+                _ => error::Client::Unhandled(err),
+            })?;
+
+        Ok(entries
+            .into_iter()
+            .filter(|entry| entry.metadata().mode() == EntryMode::FILE)
+            .map(|entry| (entry.path().to_string(), entry.metadata().content_length()))
+            .collect())
+    }
+
+    /// Copies object `src` to `dest` and returns its size.
+    ///
+    /// Backends with a native copy do it server-side, without the bytes going
+    /// through this process. Others, objects over 5 GiB, or a native copy
+    /// failing as unsupported, stream the object through it, keeping its
+    /// content type and metadata.
+    pub async fn copy(&self, src: &str, dest: &str) -> Result<u64> {
+        let meta = self
+            .inner
+            .stat(src)
+            .await
+            .map_err(|err| error::Client::Copy(src.to_string(), dest.to_string(), err))?;
+        if !meta.is_file() {
+            return Err(error::Client::NotAnObject(src.to_string()));
+        }
+
+        let copied = if self.inner.info().full_capability().copy
+            && meta.content_length() <= MAX_NATIVE_COPY_SIZE
+        {
+            let _permit = self.scheduler.acquire(0).await;
+            self.inner.copy(src, dest).await
+        } else {
+            Err(opendal::Error::new(
+                ErrorKind::Unsupported,
+                "no native copy",
+            ))
+        };
+
+        match copied {
+            Ok(()) => Ok(meta.content_length()),
+            // no native copy, or an object too large for it
+            Err(err) if err.kind() == ErrorKind::Unsupported => {
+                let _permit = self.scheduler.acquire(meta.content_length()).await;
+                let (size, _) = stream_object(&self.inner, src, &self.inner, dest, &meta).await?;
+
+                Ok(size)
+            }
+            Err(err) => Err(error::Client::Copy(src.to_string(), dest.to_string(), err)),
+        }
+    }
+
+    /// Copies `src` to `dest`, then checks that `dest` has the size of `src`
+    async fn copy_verified(&self, src: &str, dest: &str) -> Result<u64> {
+        let size = self.copy(src, dest).await?;
+        let copied = self
+            .inner
+            .stat(dest)
+            .await
+            .map_err(|err| error::Client::Copy(src.to_string(), dest.to_string(), err))?
+            .content_length();
+
+        if copied != size {
+            return Err(error::Client::CopyVerify(dest.to_string()));
+        }
+
+        Ok(size)
+    }
+
+    /// Copies object `src` to `dest` of client `target`, which may use
+    /// another profile or backend, and returns its size.
+    ///
+    /// The object is streamed through this process, keeping its content type
+    /// and metadata. The copy is then checked against the size of the source
+    /// and against the MD5 checksums either side exposes.
+    pub async fn copy_to(&self, src: &str, target: &Client, dest: &str) -> Result<u64> {
+        let copy_error = |err| error::Client::Copy(src.to_string(), dest.to_string(), err);
+
+        let meta = self.inner.stat(src).await.map_err(copy_error)?;
+        if !meta.is_file() {
+            return Err(error::Client::NotAnObject(src.to_string()));
+        }
+
+        let permit = self.scheduler.acquire(meta.content_length()).await;
+        let (size, digest) = stream_object(&self.inner, src, &target.inner, dest, &meta).await?;
+        drop(permit);
+
+        let copied = target.inner.stat(dest).await.map_err(copy_error)?;
+
+        if copied.content_length() != size
+            || !md5_matches(&meta, &digest)
+            || !md5_matches(&copied, &digest)
+        {
+            return Err(error::Client::CopyVerify(dest.to_string()));
+        }
+
+        Ok(size)
+    }
+
+    /// Moves object `src` to `dest` and returns its size.
+    ///
+    /// Backends with a native rename move it server-side, the object is
+    /// otherwise copied, verified and then deleted.
+    pub async fn rename(&self, src: &str, dest: &str) -> Result<u64> {
+        // copying then deleting the source would delete the only copy
+        if src == dest {
+            return Err(error::Client::MoveOntoItself(src.to_string()));
+        }
+
+        if !self.inner.info().full_capability().rename {
+            let size = self.copy_verified(src, dest).await?;
+            self.delete_object(src).await?;
+
+            return Ok(size);
+        }
+
+        let rename_err = |err| error::Client::Rename(src.to_string(), dest.to_string(), err);
+        let meta = self.inner.stat(src).await.map_err(rename_err)?;
+        if !meta.is_file() {
+            return Err(error::Client::NotAnObject(src.to_string()));
+        }
+
+        let _permit = self.scheduler.acquire(0).await;
+        self.inner.rename(src, dest).await.map_err(rename_err)?;
+
+        Ok(meta.content_length())
+    }
+
+    /// Plans the move of every object below remote folder `src` into remote
+    /// folder `dest`, which cannot contain one another
+    pub async fn plan_move(&self, profile: &str, src: &str, dest: &str) -> Result<MoveSession> {
+        let (src, dest) = (folder_prefix(src), folder_prefix(dest));
+        if src.starts_with(&dest) || dest.starts_with(&src) {
+            return Err(error::Client::MoveOverlap(src, dest));
+        }
+
+        Ok(MoveSession {
+            id: session_id(),
+            profile: profile.to_string(),
+            objects: self.list_objects(&src).await?.into_iter().collect(),
+            src,
+            dest,
+            pattern: None,
+            renames: BTreeMap::new(),
+            copied: BTreeSet::new(),
+            deleted: BTreeSet::new(),
+        })
+    }
+
+    /// Plans the rename of every object below remote folder `prefix` whose
+    /// key matches regex `from`, to its key with the first match replaced by
+    /// template `to`, e.g. `$1/$2`.
+    ///
+    /// Destinations claimed by several objects, or by an object that already
+    /// exists, are returned as collisions: a plan with collisions must not be
+    /// run.
+    pub async fn plan_rename(
+        &self,
+        profile: &str,
+        prefix: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<(MoveSession, Vec<RenameCollision>)> {
+        let pattern = Regex::new(from).map_err(|err| {
+            error::Client::RenameInvalidPattern(from.to_string(), err.to_string())
+        })?;
+        let src = folder_prefix(prefix);
+        let existing = self.list_objects(&src).await?;
+
+        let mut objects = BTreeMap::new();
+        let mut renames = BTreeMap::new();
+        let mut claims = BTreeMap::<String, Vec<String>>::new();
+        for (key, size) in &existing {
+            let dest = pattern.replace(key, to);
+            if dest == key.as_str() {
+                continue;
+            }
+
+            objects.insert(key.clone(), *size);
+            claims
+                .entry(dest.to_string())
+                .or_default()
+                .push(key.clone());
+            renames.insert(key.clone(), dest.into_owned());
+        }
+
+        // destinations outside `prefix` were not listed, so each is looked up
+        let outside =
+            future::try_join_all(claims.keys().filter(|dest| !dest.starts_with(&src)).map(
+                |dest| async move {
+                    let _permit = self.scheduler.acquire(0).await;
+                    match self.inner.stat(dest).await {
+                        Ok(meta) if meta.is_file() => Ok(Some(dest.clone())),
+                        Ok(_) => Ok(None),
+                        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+                        Err(err) => Err(error::Client::ListMetadata(dest.clone(), err)),
+                    }
+                },
+            ))
+            .await?;
+
+        // an existing destination would be overwritten, and deleted when it
+        // is itself renamed
+        let existing_keys = existing.into_iter().map(|(key, _)| key);
+        for key in existing_keys.chain(outside.into_iter().flatten()) {
+            if let Some(srcs) = claims.get_mut(&key) {
+                srcs.insert(0, key);
+            }
+        }
+
+        let collisions = claims
+            .into_iter()
+            .filter(|(_, srcs)| srcs.len() > 1)
+            .map(|(dest, srcs)| RenameCollision { dest, srcs })
+            .collect();
+
+        let session = MoveSession {
+            id: session_id(),
+            profile: profile.to_string(),
+            src,
+            dest: to.to_string(),
+            pattern: Some(from.to_string()),
+            renames,
+            objects,
+            copied: BTreeSet::new(),
+            deleted: BTreeSet::new(),
+        };
+
+        Ok((session, collisions))
+    }
+
+    /// Copies and verifies the objects of `session` that are not copied
+    /// yet, within the client [`TransferLimits`].
+    ///
+    /// The stream yields the outcome of each object once it completes, so
+    /// that callers can record progress.
+    pub fn move_session_copies(
+        &self,
+        session: &MoveSession,
+    ) -> Pin<Box<dyn Stream<Item = Transfer> + Send + '_>> {
+        let operations = session
+            .objects
+            .iter()
+            .filter(|(key, _)| !session.copied.contains(*key))
+            .map(|(key, size)| (key.clone(), session.dest_key(key), *size))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(move |(src, dest, size)| {
+                (size, async move {
+                    let result = self.copy_verified(&src, &dest).await;
+
+                    Transfer { src, dest, result }
+                })
+            });
+
+        self.scheduler.stream(operations).boxed()
+    }
+
+    /// Deletes the source of the objects of `session` that are copied but
+    /// not deleted yet, within the client [`TransferLimits`].
+    ///
+    /// The stream yields the outcome of each object once it completes, so
+    /// that callers can record progress.
+    pub fn move_session_deletes(
+        &self,
+        session: &MoveSession,
+    ) -> Pin<Box<dyn Stream<Item = Transfer> + Send + '_>> {
+        let operations = session
+            .copied
+            .iter()
+            .filter(|key| !session.deleted.contains(*key))
+            .map(|key| (key.clone(), session.dest_key(key)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(move |(src, dest)| {
+                (0, async move {
+                    let result = self.delete_object(&src).await.map(|_| 0);
+
+                    Transfer { src, dest, result }
+                })
+            });
+
+        self.scheduler.stream(operations).boxed()
+    }
+
+    /// Copies every object below remote folder `src` into remote folder
+    /// `dest`, keeping the folder tree, within the client [`TransferLimits`].
+    ///
+    /// Directory markers are skipped. A failing object does not abort the
+    /// others: each outcome is reported in the returned [`TransferReport`].
+    pub async fn copy_dir(&self, src: &str, dest: &str) -> Result<TransferReport> {
+        self.copy_objects(src, dest, |src, dest| async move {
+            self.copy(&src, &dest).await
+        })
+        .await
+    }
+
+    /// Same as [`Client::copy_dir`] into folder `dest` of client `target`,
+    /// each object being copied and verified by [`Client::copy_to`].
+    pub async fn copy_dir_to(
+        &self,
+        src: &str,
+        target: &Client,
+        dest: &str,
+    ) -> Result<TransferReport> {
+        self.copy_objects(src, dest, |src, dest| async move {
+            self.copy_to(&src, target, &dest).await
+        })
+        .await
+    }
+
+    /// Runs `copy(src, dest)` for every object below remote folder `src`,
+    /// mapped into remote folder `dest`
+    async fn copy_objects<F, Fut>(&self, src: &str, dest: &str, copy: F) -> Result<TransferReport>
+    where
+        F: Fn(String, String) -> Fut,
+        Fut: Future<Output = Result<u64>>,
+    {
+        let src = folder_prefix(src);
+        let dest = folder_prefix(dest);
+        let objects = self.list_objects(&src).await?;
+
+        let operations = objects.into_iter().map(|(path, size)| {
+            let (src, dest, copy) = (src.as_str(), dest.as_str(), &copy);
+
+            (size, async move {
+                let relative = path.strip_prefix(src).unwrap_or(&path);
+                let dest = format!("{dest}{relative}");
+                let result = copy(path.clone(), dest.clone()).await;
+
+                Transfer {
+                    src: path,
+                    dest,
+                    result,
+                }
+            })
+        });
+
+        Ok(TransferReport {
+            transfers: self.scheduler.run(operations).await,
+            ..Default::default()
+        })
+    }
+
+    /// Downloads every `(path, size)` object into local directory `dest`,
+    /// at its path relative to remote folder `prefix`, within the client
+    /// [`TransferLimits`].
+    ///
+    /// A failing object does not abort the others: each outcome is reported
+    /// in the returned [`TransferReport`].
+    pub async fn download_many(
+        &self,
+        prefix: &str,
+        objects: Vec<(String, u64)>,
+        dest: &Path,
+        options: &DownloadOptions,
+    ) -> TransferReport {
+        let prefix = prefix.trim_start_matches('/');
+        let operations = objects.into_iter().map(|(path, size)| {
+            (size, async move {
+                let relative = path.strip_prefix(prefix).unwrap_or(&path);
+                let file = local_path(dest, relative);
+                let result = match &file {
+                    Some(file) => self.download_into(&path, file, options).await,
+                    None => Err(error::Client::DownloadInvalidPath(path.clone())),
+                };
+
+                Transfer {
+                    src: path,
+                    dest: file.unwrap_or_default().display().to_string(),
+                    result,
+                }
+            })
+        });
+
+        TransferReport {
+            transfers: self.scheduler.run(operations).await,
+            ..Default::default()
+        }
+    }
+
+    /// Like [`Client::download_file_with`], creating the parent directories of `dest`
+    async fn download_into(
+        &self,
+        path: &str,
+        dest: &Path,
+        options: &DownloadOptions,
+    ) -> Result<u64> {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|err| error::Client::DownloadWrite(parent.display().to_string(), err))?;
+        }
+
+        self.download_file_with(path, dest, options).await
+    }
+
+    async fn download_range(
+        &self,
+        path: &str,
+        dest: &Path,
+        range: Range<u64>,
+    ) -> Result<Range<u64>> {
+        let _permit = self.scheduler.acquire(range.end - range.start).await;
+        let buffer = self
+            .inner
+            .read_with(path)
+            .range(range.clone())
+            .await
+            .map_err(error::Client::Download)?;
+
+        let write_err = |err| error::Client::DownloadWrite(dest.display().to_string(), err);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(dest)
+            .await
+            .map_err(write_err)?;
+        file.seek(SeekFrom::Start(range.start))
+            .await
+            .map_err(write_err)?;
+
+        for bytes in buffer {
+            file.write_all(&bytes).await.map_err(write_err)?;
+        }
+
+        file.flush().await.map_err(write_err)?;
+
+        Ok(range)
+    }
+
+    pub async fn upload(
+        &self,
+        src: &str,
+        dest: &str,
+        content_type: Option<&str>,
+    ) -> Result<MarkerErrors> {
+        let options = UploadOptions {
+            content_type: content_type.map(String::from),
+            ..Default::default()
+        };
+
+        self.upload_with(src, &UploadTarget::Prefix(dest.to_string()), &options)
+            .await
+    }
+
+    /// Uploads the local file `src` to `target`, streaming it from disk in
+    /// chunks of `options.chunk_size` bytes.
+    ///
+    /// Backends supporting it (multipart on S3, resumable on GCS) receive up
+    /// to `options.concurrent` parts at the same time.
+    ///
+    /// Returns the folder markers that could not be created above the
+    /// uploaded key.
+    pub async fn upload_with(
+        &self,
+        src: &str,
+        target: &UploadTarget,
+        options: &UploadOptions,
+    ) -> Result<MarkerErrors> {
+        let dest = target.key(src)?;
+        let file = File::open(src)
+            .await
+            .map_err(error::Client::UploadFileNotFound)?;
+        let size = file
+            .metadata()
+            .await
+            .map_err(|err| error::Client::UploadLoad(src.to_string(), err))?
+            .len();
+
+        self.write_from(src, file, size, &dest, options).await?;
+
+        Ok(self.create_markers([dest.as_str()]).await)
+    }
+
+    /// Uploads every file below local directory `src` into folder `dest`,
+    /// mirroring their relative paths, within the client [`TransferLimits`].
+    ///
+    /// A failing file does not abort the others: each outcome is reported in
+    /// the returned [`TransferReport`], along with the folder markers that
+    /// could not be created.
+    pub async fn upload_dir(
+        &self,
+        src: &Path,
+        dest: &str,
+        options: &UploadOptions,
+    ) -> Result<TransferReport> {
+        options.validate()?;
+
+        let files = walk_local(src)
+            .await
+            .map_err(|err| error::Client::UploadLoad(src.display().to_string(), err))?;
+
+        let operations = files.into_iter().map(|(file, size)| {
+            (*size.as_ref().unwrap_or(&0), async move {
+                let relative = file.strip_prefix(src).unwrap_or(&file);
+                let key = join_key(dest, relative);
+                let src = file.display().to_string();
+                let result = match size {
+                    Ok(size) => match File::open(&file).await {
+                        Ok(reader) => self.write_from(&src, reader, size, &key, options).await,
+                        Err(err) => Err(error::Client::UploadFileNotFound(err)),
+                    },
+                    Err(err) => Err(error::Client::UploadLoad(src.clone(), err)),
+                };
+
+                Transfer {
+                    src,
+                    dest: key,
+                    result,
+                }
+            })
+        });
+
+        let mut report = TransferReport {
+            transfers: self.scheduler.run(operations).await,
+            ..Default::default()
+        };
+        report.marker_errors = self
+            .create_markers(report.succeeded().map(|transfer| transfer.dest.as_str()))
+            .await;
+
+        Ok(report)
+    }
+
+    /// Streams `reader` into the object `dest`, in chunks of
+    /// `options.chunk_size` bytes, until it is exhausted.
+    ///
+    /// Its length being unknown, it only counts against the number of jobs
+    /// of the client [`TransferLimits`].
+    ///
+    /// Returns the number of bytes written, and the folder markers that could
+    /// not be created above `dest`.
+    pub async fn upload_from<R>(
+        &self,
+        reader: R,
+        dest: &str,
+        options: &UploadOptions,
+    ) -> Result<(u64, MarkerErrors)>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let written = self.write_from(dest, reader, 0, dest, options).await?;
+
+        Ok((written, self.create_markers([dest]).await))
+    }
+
+    /// Starts the resumable upload of local file `src` to `target`, as a
+    /// multipart upload of the backend.
+    ///
+    /// Parts are `options.chunk_size` bytes, raised as needed to the smallest
+    /// part and largest number of parts that backends accept.
+    pub async fn create_upload_session(
+        &self,
+        profile: &str,
+        src: &str,
+        target: &UploadTarget,
+        options: &UploadOptions,
+    ) -> Result<UploadSession> {
+        options.validate()?;
+
+        let dest = target.key(src)?;
+        let src = fs::canonicalize(src)
+            .await
+            .map_err(error::Client::UploadFileNotFound)?;
+        let meta = fs::metadata(&src)
+            .await
+            .map_err(error::Client::UploadFileNotFound)?;
+        let modified = meta
+            .modified()
+            .map_err(|err| error::Client::UploadLoad(src.display().to_string(), err))?;
+        let upload_id = self
+            .multipart()?
+            .create(&dest, options.content_type.as_deref())
+            .await
+            .map_err(|err| error::Client::UploadWrite(dest.clone(), err))?;
+
+        Ok(UploadSession {
+            id: session_id(),
+            profile: profile.to_string(),
+            src,
+            dest,
+            size: meta.len(),
+            modified,
+            part_size: (options.chunk_size as u64)
+                .max(MIN_PART_SIZE)
+                .max(meta.len().div_ceil(MAX_PARTS)),
+            content_type: options.content_type.clone(),
+            upload_id,
+            completed: BTreeMap::new(),
+        })
+    }
+
+    /// Uploads the parts of `session` that are not yet completed, at most
+    /// `concurrent` at a time.
+    ///
+    /// The stream yields the index and ETag of each part once it is uploaded,
+    /// so that callers can record progress before the next part completes.
+    pub fn upload_session_parts(
+        &self,
+        session: &UploadSession,
+        concurrent: usize,
+    ) -> Pin<Box<dyn Stream<Item = Result<UploadedPart>> + Send + '_>> {
+        let session = Arc::new(session.clone());
+        let parts = (0..session.parts())
+            .filter(|index| !session.completed.contains_key(index))
+            .collect::<Vec<_>>();
+
+        stream::iter(parts)
+            .map(move |index| {
+                let session = session.clone();
+                async move {
+                    let etag = self.upload_part(&session, index).await?;
+                    Ok((index, etag))
+                }
+            })
+            .buffer_unordered(concurrent.max(1))
+            .boxed()
+    }
+
+    /// Uploads part `index` of `session` and returns its ETag
+    async fn upload_part(&self, session: &UploadSession, index: u64) -> Result<String> {
+        let (offset, len) = session.part_range(index);
+        let _permit = self.scheduler.acquire(len).await;
+
+        let src = session.src.display().to_string();
+        let mut file = File::open(&session.src)
+            .await
+            .map_err(error::Client::UploadFileNotFound)?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|err| error::Client::UploadLoad(src.clone(), err))?;
+
+        let mut buffer = Vec::with_capacity(len as usize);
+        file.take(len)
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(|err| error::Client::UploadLoad(src, err))?;
+
+        self.multipart()?
+            .upload_part(&session.dest, &session.upload_id, index, buffer)
+            .await
+            .map_err(|err| error::Client::UploadWrite(session.dest.clone(), err))
+    }
+
+    /// Assembles the uploaded parts of a fully uploaded `session` into its
+    /// destination, server-side, and returns the folder markers that could
+    /// not be created above it
+    pub async fn complete_upload_session(&self, session: &UploadSession) -> Result<MarkerErrors> {
+        self.multipart()?
+            .complete(&session.dest, &session.upload_id, &session.completed)
+            .await
+            .map_err(|err| error::Client::UploadComplete(session.dest.clone(), err))?;
+
+        Ok(self.create_markers([session.dest.as_str()]).await)
+    }
+
+    /// Discards the multipart upload of `session` and its uploaded parts
+    pub async fn abort_upload_session(&self, session: &UploadSession) -> Result<()> {
+        let _permit = self.scheduler.acquire(0).await;
+
+        self.multipart()?
+            .abort(&session.dest, &session.upload_id)
+            .await
+            .map_err(|err| error::Client::UploadAbort(session.dest.clone(), err))
+    }
+
+    /// Multipart uploads of the backend, failing if it has none
+    fn multipart(&self) -> Result<&Multipart> {
+        self.multipart
+            .as_ref()
+            .ok_or(error::Client::UploadResumeUnsupported)
+    }
+
+    /// Streams `reader`, of `size` bytes, into the object `dest`
+    async fn write_from<R>(
+        &self,
+        src: &str,
+        mut reader: R,
+        size: u64,
+        dest: &str,
+        options: &UploadOptions,
+    ) -> Result<u64>
+    where
+        R: AsyncRead + Unpin,
+    {
+        options.validate()?;
+
+        let _permit = self.scheduler.acquire(size).await;
+        let mut writer = opendal_builder!(
+            self.inner
+                .writer_with(dest)
+                .chunk(options.chunk_size)
+                .concurrent(options.concurrent),
+            options.content_type.as_deref() => content_type
+        )
+        .await
+        .map_err(|err| error::Client::UploadWrite(dest.to_string(), err))?;
+
+        let mut buffer = vec![0; options.chunk_size];
+        let mut written = 0;
+
+        loop {
+            let read = match reader.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) => {
+                    let _ = writer.abort().await;
+                    return Err(error::Client::UploadLoad(src.to_string(), err));
+                }
+            };
+
+            if let Err(err) = writer.write(Bytes::copy_from_slice(&buffer[..read])).await {
+                let _ = writer.abort().await;
+                return Err(error::Client::UploadWrite(dest.to_string(), err));
+            }
+            written += read as u64;
+        }
+
+        writer
+            .close()
+            .await
+            .map_err(|err| error::Client::UploadWrite(dest.to_string(), err))?;
+
+        Ok(written)
+    }
+
+    /// Deletes exactly the objects `keys`, within the client
+    /// [`TransferLimits`].
+    ///
+    /// Unlike [`Client::delete`], keys sharing one of `keys` as a prefix are
+    /// left untouched.
+    pub async fn delete_objects(&self, keys: Vec<String>) -> TransferReport {
+        let operations = keys.into_iter().map(|key| {
+            (0, async move {
+                let result = self.delete_object(&key).await.map(|_| 0);
+
+                Transfer {
+                    src: key,
+                    dest: String::new(),
+                    result,
+                }
+            })
+        });
+
+        TransferReport {
+            transfers: self.scheduler.run(operations).await,
+            ..Default::default()
+        }
+    }
+
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        let _permit = self.scheduler.acquire(0).await;
+        self.inner
+            .remove_all(path)
+            .await
+            .map_err(|error| error::Client::Delete {
+                path: path.to_string(),
+                error,
+            })
+    }
+
+    /// Deletes the object `key` alone
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        let _permit = self.scheduler.acquire(0).await;
+        self.inner
+            .delete(key)
+            .await
+            .map_err(|error| error::Client::Delete {
+                path: key.to_string(),
+                error,
+            })
+    }
+}
+
+impl TryFrom<GCSConfig> for Client {
+    type Error = error::Client;
+
+    fn try_from(value: GCSConfig) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            multipart: Some((&value).try_into().map_err(error::Client::Initialization)?),
+            inner: value.try_into().map_err(error::Client::Initialization)?,
+            scheduler: Scheduler::default(),
+            directory_markers: false,
+        })
+    }
+}
+
+impl TryFrom<S3Config> for Client {
+    type Error = error::Client;
+
+    fn try_from(value: S3Config) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            multipart: Some((&value).try_into().map_err(error::Client::Initialization)?),
+            inner: value.try_into().map_err(error::Client::Initialization)?,
+            scheduler: Scheduler::default(),
+            directory_markers: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Memory;
+
+    fn memory_client() -> Client {
+        Client {
+            inner: Operator::new(Memory::default()).unwrap().finish(),
+            multipart: None,
+            scheduler: Scheduler::default(),
+            directory_markers: false,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_stream_download_into_writer() {
+        let client = memory_client();
+        let content = "all good 👌!".repeat(1024);
+        client
+            .inner
+            .write("a/file.txt", content.clone())
+            .await
+            .unwrap();
+
+        let mut buffer: Vec<u8> = vec![];
+        let written = client.download_to("a/file.txt", &mut buffer).await.unwrap();
+
+        assert_eq!(content.len() as u64, written);
+        assert_eq!(Ok(content.as_str()), String::from_utf8(buffer).as_deref());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_resume_listing_after_cursor() {
+        let client = memory_client();
+        for name in ["a", "b", "c", "d", "e"] {
+            client
+                .inner
+                .write(&format!("dir/{name}"), name)
+                .await
+                .unwrap();
+        }
+
+        let first = client
+            .list("dir/", Some(2))
+            .await
+            .unwrap()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some("dir/b"), first.cursor.as_deref());
+
+        let names = client
+            .list_with(
+                "dir/",
+                &ListOptions {
+                    limit: Some(2),
+                    start_after: first.cursor,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap()
+            .map_ok(|page| page.entries.into_iter().map(|entry| entry.name))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["c", "d", "e"], names);
+
+        assert!(client.list("dir/", Some(0)).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_list_entries_with_metadata() {
+        let client = memory_client();
+        client
+            .inner
+            .write_with("dir/a.txt", "hello")
+            .content_type("text/plain")
+            .await
+            .unwrap();
+
+        let page = client
+            .list("dir/", None)
+            .await
+            .unwrap()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+
+        let entry = &page.entries[0];
+        assert_eq!(1, page.entries.len());
+        assert_eq!("dir/a.txt", entry.path);
+        assert_eq!("a.txt", entry.name);
+        assert_eq!(EntryMode::FILE, entry.mode);
+        assert_eq!(Some(5), entry.content_length);
+        assert_eq!(Some("text/plain"), entry.content_type.as_deref());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_filter_listing_by_key() {
+        let client = memory_client();
+        for path in [
+            "events/2023-12/a.parquet",
+            "events/2024-01/b.parquet",
+            "events/2024-01/c.csv",
+            "events/2024-02/nested/d.parquet",
+            "other/e.parquet",
+        ] {
+            client.inner.write(path, "data").await.unwrap();
+        }
+
+        let list = |filter: KeyFilter| {
+            let client = &client;
+            async move {
+                let options = ListOptions {
+                    limit: Some(10),
+                    filter: Some(filter),
+                    ..Default::default()
+                };
+
+                client
+                    .list_with("/", &options)
+                    .await
+                    .unwrap()
+                    .map_ok(|page| page.entries.into_iter().map(|entry| entry.path))
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            vec!["events/2024-01/b.parquet"],
+            list(KeyFilter::glob("events/2024-*/*.parquet").unwrap()).await
+        );
+        assert_eq!(
+            vec![
+                "events/2024-01/b.parquet",
+                "events/2024-02/nested/d.parquet"
+            ],
+            list(KeyFilter::glob("events/2024-*/**/*.parquet").unwrap()).await
+        );
+        assert_eq!(
+            vec!["events/2023-12/a.parquet", "other/e.parquet"],
+            list(KeyFilter::regex(r"(12|^other)/.*\.parquet$").unwrap()).await
+        );
+    }
+
+    #[test]
+    fn should_push_filter_prefix_down() {
+        let glob = KeyFilter::glob("/events/2024-*/*.parquet").unwrap();
+        assert_eq!("events/", glob.folder("/"));
+        assert_eq!("events/", glob.folder("events/"));
+        assert_eq!("other/", glob.folder("other/"));
+
+        let regex = KeyFilter::regex(r"^events/2024/0?1/.*").unwrap();
+        assert_eq!("events/2024/", regex.folder("/"));
+        assert_eq!("/", KeyFilter::regex("events/.*").unwrap().folder("/"));
+        assert_eq!(
+            "/",
+            KeyFilter::regex("^events/x|other").unwrap().folder("/")
+        );
+        assert_eq!(
+            "events/",
+            KeyFilter::regex("^events/(x|other)").unwrap().folder("/")
+        );
+        assert!(KeyFilter::glob("events/[").is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_find_objects_matching_every_condition() {
+        let client = memory_client();
+        for (path, content, content_type) in [
+            (
+                "logs/a.json",
+                "0123456789",
+                "application/json; charset=utf-8",
+            ),
+            ("logs/nested/b.json", "0123456789", "application/json"),
+            ("logs/c.json", "0", "application/json"),
+            ("logs/d.txt", "0123456789", "text/plain"),
+            ("logs-old/e.json", "0123456789", "application/json"),
+        ] {
+            client
+                .inner
+                .write_with(path, content)
+                .content_type(content_type)
+                .await
+                .unwrap();
+        }
+
+        let options = FindOptions {
+            larger_than: Some(5),
+            content_type: Some(String::from("application/json")),
+            ..Default::default()
+        };
+        let mut found = client
+            .find("logs", &options)
+            .await
+            .unwrap()
+            .map_ok(|object| object.path)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        found.sort();
+
+        assert_eq!(vec!["logs/a.json", "logs/nested/b.json"], found);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_delete_exact_objects() {
+        let client = memory_client();
+        for path in ["logs/a.json", "logs/a.json.bak", "logs/a.json/nested"] {
+            client.inner.write(path, path).await.unwrap();
+        }
+
+        let report = client
+            .delete_objects(vec![String::from("logs/a.json")])
+            .await;
+        assert_eq!(1, report.succeeded().count());
+        assert!(!client.inner.is_exist("logs/a.json").await.unwrap());
+        assert!(client.inner.is_exist("logs/a.json.bak").await.unwrap());
+        assert!(client.inner.is_exist("logs/a.json/nested").await.unwrap());
+    }
+
+    #[test]
+    fn should_match_objects_by_age_and_media_type_family() {
+        let now = Utc::now();
+        let meta = ObjectMeta {
+            path: String::from("logs/a.txt"),
+            name: String::from("a.txt"),
+            mode: EntryMode::FILE,
+            content_length: Some(1),
+            content_type: Some(String::from("text/plain")),
+            last_modified: Some(now - chrono::Duration::days(2)),
+            etag: None,
+            content_md5: None,
+            cache_control: None,
+            content_disposition: None,
+            version: None,
+            user_metadata: HashMap::new(),
+        };
+        let options = |older_than: u64, content_type: &str| FindOptions {
+            older_than: Some(Duration::from_secs(older_than * 24 * 60 * 60)),
+            content_type: Some(content_type.to_string()),
+            ..Default::default()
+        };
+
+        assert!(options(1, "text/*").matches(&meta, now));
+        assert!(!options(3, "text/*").matches(&meta, now));
+        assert!(!options(1, "image/*").matches(&meta, now));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_sum_disk_usage_by_folder() {
+        let client = memory_client();
+        for (path, size) in [
+            ("data/a/x", 10),
+            ("data/a/deep/y", 20),
+            ("data/b/z", 50),
+            ("data/top", 1),
+        ] {
+            client.inner.write(path, vec![0; size]).await.unwrap();
+        }
+
+        let usages = |usages: Vec<Usage>| {
+            usages
+                .into_iter()
+                .map(|usage| (usage.prefix, usage.size, usage.objects))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![
+                (String::from("data/"), 81, 4),
+                (String::from("data/b/"), 50, 1),
+                (String::from("data/a/"), 30, 2),
+            ],
+            usages(client.disk_usage("data/", 1).await.unwrap())
+        );
+        assert_eq!(
+            vec![(String::from("data/"), 81, 4)],
+            usages(client.disk_usage("data/", 0).await.unwrap())
+        );
+        assert_eq!(4, client.disk_usage("data/", 2).await.unwrap().len());
+
+        // the listed folder comes first even when a sub-folder ties with it
+        let client = memory_client();
+        client.inner.write("+a/x", vec![0; 5]).await.unwrap();
+        assert_eq!(
+            vec![(String::from("/"), 5, 1), (String::from("+a/"), 5, 1)],
+            usages(client.disk_usage("/", 1).await.unwrap())
+        );
+
+        // sibling folders sharing the name as a prefix are left out
+        let client = memory_client();
+        client.inner.write("logs/a/x", vec![0; 3]).await.unwrap();
+        client.inner.write("logs-old/y", vec![0; 7]).await.unwrap();
+        assert_eq!(
+            vec![
+                (String::from("logs/"), 3, 1),
+                (String::from("logs/a/"), 3, 1)
+            ],
+            usages(client.disk_usage("logs", 1).await.unwrap())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_export_inventory() {
+        let (multipart, bucket) = crate::multipart::tests::mock_bucket().await;
+        let client = Client {
+            multipart: Some(multipart),
+            ..memory_client()
+        };
+        // listed one per page, with an object missing from the inventory
+        for key in ["root/data/0.txt", "root/data/a.txt"] {
+            bucket
+                .lock()
+                .unwrap()
+                .objects
+                .insert(key.to_string(), b"hello".to_vec());
+        }
+        client
+            .inner
+            .write_with("data/a.txt", "hello")
+            .content_type("text/plain; charset=\"utf-8\"")
+            .await
+            .unwrap();
+        client.inner.write("data/nested/b,c", "0").await.unwrap();
+
+        let mut csv = vec![];
+        let count = client
+            .export_inventory("data/", InventoryFormat::Csv, &mut csv)
+            .await
+            .unwrap();
+        assert_eq!(2, count);
+        assert_eq!(
+            "key,size,contentType,lastModified,etag,storageClass\n\
+             data/a.txt,5,\"text/plain; charset=\"\"utf-8\"\"\",,,STANDARD_IA\n\
+             \"data/nested/b,c\",1,,,,\n",
+            String::from_utf8(csv).unwrap()
+        );
+
+        let mut jsonl = vec![];
+        client
+            .export_inventory("data/", InventoryFormat::Jsonl, &mut jsonl)
+            .await
+            .unwrap();
+        let lines = String::from_utf8(jsonl).unwrap();
+        let records = lines
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(2, records.len());
+        assert_eq!("data/a.txt", records[0]["key"]);
+        assert_eq!(5, records[0]["size"]);
+        assert_eq!("STANDARD_IA", records[0]["storageClass"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_stream_copies_without_native_copy() {
+        let client = memory_client();
+        client
+            .inner
+            .write_with("src/a.json", "{}")
+            .content_type("application/json")
+            .await
+            .unwrap();
+        client
+            .inner
+            .write("src/nested/b.bin", vec![7; 100])
+            .await
+            .unwrap();
+
+        assert_eq!(2, client.copy("src/a.json", "copy.json").await.unwrap());
+        let meta = client.stat("copy.json").await.unwrap();
+        assert_eq!(Some("application/json"), meta.content_type.as_deref());
+        assert!(matches!(
+            client.copy("src/", "folder/").await,
+            Err(error::Client::NotAnObject(_))
+        ));
+
+        let report = client.copy_dir("src", "dest/").await.unwrap();
+        assert_eq!(2, report.succeeded().count());
+        assert_eq!(102, report.bytes());
+        assert_eq!(
+            vec![7; 100],
+            client.download("dest/nested/b.bin").await.unwrap()
+        );
+        assert_eq!(
+            b"{}".to_vec(),
+            client.download("dest/a.json").await.unwrap()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_copy_between_clients() {
+        let (source, target) = (memory_client(), memory_client());
+        source
+            .inner
+            .write_with("data/a.json", "{}")
+            .content_type("application/json")
+            .await
+            .unwrap();
+        source
+            .inner
+            .write("data/nested/b.bin", vec![7; 100])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            2,
+            source
+                .copy_to("data/a.json", &target, "a.json")
+                .await
+                .unwrap()
+        );
+        let meta = target.stat("a.json").await.unwrap();
+        assert_eq!(Some("application/json"), meta.content_type.as_deref());
+        assert!(!source.inner.is_exist("a.json").await.unwrap());
+
+        let report = source
+            .copy_dir_to("data/", &target, "archive/data/")
+            .await
+            .unwrap();
+        assert_eq!(2, report.succeeded().count());
+        assert_eq!(102, report.bytes());
+        assert_eq!(
+            vec![7; 100],
+            target.download("archive/data/nested/b.bin").await.unwrap()
+        );
+    }
+
+    #[test]
+    fn should_verify_exposed_md5_only() {
+        let digest = Md5::digest(b"hello").to_vec();
+        let mut meta = Metadata::new(EntryMode::FILE);
+        assert!(md5_matches(&meta, &digest));
+
+        meta.set_content_md5("XUFAKrxLKna5cZ2REBfFkg==");
+        assert!(md5_matches(&meta, &digest));
+        assert!(!md5_matches(&meta, &Md5::digest(b"world")));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_rename_object_without_native_rename() {
+        let client = memory_client();
+        client.inner.write("a.txt", "hello").await.unwrap();
+
+        assert!(matches!(
+            client.rename("a.txt", "a.txt").await,
+            Err(error::Client::MoveOntoItself(_))
+        ));
+        assert!(client.inner.is_exist("a.txt").await.unwrap());
+
+        assert_eq!(5, client.rename("a.txt", "b.txt").await.unwrap());
+        assert!(!client.inner.is_exist("a.txt").await.unwrap());
+        assert_eq!(b"hello".to_vec(), client.download("b.txt").await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_resume_folder_move() {
+        let client = memory_client();
+        for path in ["src/a", "src/nested/b", "src/nested/c"] {
+            client.inner.write(path, path).await.unwrap();
+        }
+        assert!(matches!(
+            client.plan_move("default", "src", "src/nested").await,
+            Err(error::Client::MoveOverlap(_, _))
+        ));
+
+        let mut session = client.plan_move("default", "src", "dest/").await.unwrap();
+        assert!(session.matches("default", "src/", "dest"));
+        assert_eq!("dest/nested/b", session.dest_key("src/nested/b"));
+
+        // as if interrupted after the first copy
+        session.copied.insert(String::from("src/a"));
+        let copied = client
+            .move_session_copies(&session)
+            .map(|transfer| transfer.result.map(|_| transfer.src))
+            .try_collect::<BTreeSet<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            BTreeSet::from([String::from("src/nested/b"), String::from("src/nested/c")]),
+            copied
+        );
+        assert!(!client.inner.is_exist("dest/a").await.unwrap());
+
+        session.copied.extend(copied);
+        let deleted = client
+            .move_session_deletes(&session)
+            .map(|transfer| transfer.result.map(|_| transfer.src))
+            .try_collect::<BTreeSet<_>>()
+            .await
+            .unwrap();
+        session.deleted.extend(deleted);
+
+        assert!(session.is_complete());
+        assert!(client.list_objects("src/").await.unwrap().is_empty());
+        assert_eq!(
+            b"src/nested/c".to_vec(),
+            client.download("dest/nested/c").await.unwrap()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_plan_bulk_rename() {
+        let client = memory_client();
+        for path in [
+            "logs/2024-10-17/app.log",
+            "logs/2024-10-17/db.log",
+            "logs/2024-10-18/app.log",
+            "logs/readme.txt",
+        ] {
+            client.inner.write(path, path).await.unwrap();
+        }
+        let (from, to) = (
+            r"^logs/(\d{4})-(\d\d)-(\d\d)/(\w+)\.log$",
+            "logs/$4/$1/$2/$3.log",
+        );
+
+        let (session, collisions) = client
+            .plan_rename("default", "logs", from, to)
+            .await
+            .unwrap();
+        assert!(collisions.is_empty());
+        assert!(session.matches_rename("default", "logs/", from, to));
+        assert!(!session.matches("default", "logs/", to));
+        assert_eq!(3, session.objects.len());
+        assert_eq!(
+            "logs/db/2024/10/17.log",
+            session.dest_key("logs/2024-10-17/db.log")
+        );
+
+        let copied = client
+            .move_session_copies(&session)
+            .map(|transfer| transfer.result.map(|_| transfer.src))
+            .try_collect::<BTreeSet<_>>()
+            .await
+            .unwrap();
+        assert_eq!(3, copied.len());
+        assert_eq!(
+            b"logs/2024-10-18/app.log".to_vec(),
+            client.download("logs/app/2024/10/18.log").await.unwrap()
+        );
+
+        let (_, collisions) = client
+            .plan_rename("default", "logs/", r"^logs/\d{4}-[^/]+/", "logs/all/")
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![RenameCollision {
+                dest: String::from("logs/all/app.log"),
+                srcs: vec![
+                    String::from("logs/2024-10-17/app.log"),
+                    String::from("logs/2024-10-18/app.log"),
+                ],
+            }],
+            collisions
+        );
+
+        // destinations outside the prefix are checked too
+        client
+            .inner
+            .write("archive/readme.txt", "old")
+            .await
+            .unwrap();
+        let (_, collisions) = client
+            .plan_rename("default", "logs/", r"^logs/(readme\.txt)$", "archive/$1")
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![RenameCollision {
+                dest: String::from("archive/readme.txt"),
+                srcs: vec![
+                    String::from("archive/readme.txt"),
+                    String::from("logs/readme.txt"),
+                ],
+            }],
+            collisions
+        );
+        assert!(matches!(
+            client.plan_rename("default", "logs/", "(", "x").await,
+            Err(error::Client::RenameInvalidPattern(_, _))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_handle_folders_alike_with_or_without_markers() {
+        let client = memory_client();
+        client.inner.write("implicit/a.txt", "a").await.unwrap();
+        client.mkdir("marked").await.unwrap();
+
+        for path in ["implicit", "implicit/", "marked/"] {
+            assert!(client.stat(path).await.unwrap().is_dir(), "{path}");
+        }
+        assert!(matches!(
+            client.stat("missing/").await,
+            Err(error::Client::StatNotFound(_))
+        ));
+
+        client.touch("marked/empty.txt").await.unwrap();
+        assert!(client
+            .download("marked/empty.txt")
+            .await
+            .unwrap()
+            .is_empty());
+        client.touch("implicit/a.txt").await.unwrap();
+        assert_eq!(
+            b"a".to_vec(),
+            client.download("implicit/a.txt").await.unwrap()
+        );
+        assert_eq!(
+            vec!["implicit/a.txt", "marked/", "marked/empty.txt"],
+            stored_keys(&client).await
+        );
+
+        let client = memory_client().with_directory_markers(true);
+        let (_, marker_errors) = client
+            .upload_from(&b"{}"[..], "a/b/c.json", &UploadOptions::default())
+            .await
+            .unwrap();
+        assert!(marker_errors.is_empty());
+        assert_eq!(vec!["a/", "a/b/", "a/b/c.json"], stored_keys(&client).await);
+    }
+
+    /// Keys actually stored, folders only existing with a marker
+    async fn stored_keys(client: &Client) -> Vec<String> {
+        let mut keys = client
+            .inner
+            .list_with("")
+            .recursive(true)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.path().to_string())
+            .collect::<Vec<_>>();
+        keys.sort();
+
+        keys
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_walk_depth_first() {
+        let client = memory_client();
+        for path in ["root/a/x.txt", "root/a/b/y.txt", "root/c.txt"] {
+            client.inner.write(path, "data").await.unwrap();
+        }
+
+        let paths = |entries: Vec<ObjectMeta>| {
+            entries
+                .into_iter()
+                .map(|entry| {
+                    (
+                        entry.relative_path("root/").to_string(),
+                        entry.depth("root/"),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let entries = client
+            .walk("root/", None)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                (String::from("a/"), 1),
+                (String::from("a/b/"), 2),
+                (String::from("a/b/y.txt"), 3),
+                (String::from("a/x.txt"), 2),
+                (String::from("c.txt"), 1),
+            ],
+            paths(entries)
+        );
+
+        let entries = client
+            .walk("root/", Some(1))
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![(String::from("a/"), 1), (String::from("c.txt"), 1)],
+            paths(entries)
+        );
+
+        let entries = client
+            .walk_after("root/", None, Some("root/a/b/y.txt"))
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![(String::from("a/x.txt"), 2), (String::from("c.txt"), 1)],
+            paths(entries)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_serialize_stat_metadata() {
+        let client = memory_client();
+        client.inner.write("dir/a.txt", "hello").await.unwrap();
+
+        let meta = client.stat("dir/a.txt").await.unwrap();
+        let json = serde_json::to_value(&meta).unwrap();
+
+        assert_eq!("dir/a.txt", json["path"]);
+        assert_eq!("a.txt", json["name"]);
+        assert_eq!("file", json["mode"]);
+        assert_eq!(5, json["contentLength"]);
+        assert_eq!(meta, serde_json::from_value(json).unwrap());
+        assert!(matches!(
+            client.stat("dir/missing.txt").await,
+            Err(error::Client::StatNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn should_split_ranges() {
+        assert_eq!(Vec::<Range<u64>>::new(), split_ranges(0, 4));
+        assert_eq!(vec![0..4, 4..8], split_ranges(8, 4));
+        assert_eq!(vec![0..4, 4..8, 8..9], split_ranges(9, 4));
+        assert_eq!(vec![0..3], split_ranges(3, 4));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn should_download_ranges_at_their_offset() {
+        let client = memory_client();
+        let content = "all good 👌!".repeat(1024);
+        client
+            .inner
+            .write("a/file.txt", content.clone())
+            .await
+            .unwrap();
+
+        let folder = assert_fs::TempDir::new().unwrap();
+        let dest = folder.path().join("file.txt");
+        let options = DownloadOptions {
+            part_size: 1000,
+            concurrent: 3,
+            threshold: 0,
+            resume: false,
+        };
+
+        let written = client
+            .download_file_with("a/file.txt", &dest, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(content.len() as u64, written);
+        assert_eq!(content, std::fs::read_to_string(&dest).unwrap());
+
+        let options = DownloadOptions {
+            part_size: 0,
+            ..options
+        };
+        let written = client
+            .download_file_with("a/file.txt", &dest, &options)
+            .await;
+        assert!(matches!(
+            written,
+            Err(error::Client::DownloadInvalidOptions(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_resume_missing_ranges_only() {
+        let client = memory_client();
+        let content = "0123456789".repeat(3);
+        client
+            .inner
+            .write("a/file.txt", content.clone())
+            .await
+            .unwrap();
+
+        let folder = assert_fs::TempDir::new().unwrap();
+        let dest = folder.path().join("file.txt");
+        let partial_path = with_suffix(&dest, PARTIAL_DOWNLOAD_SUFFIX);
+        let state_path = with_suffix(&dest, PARTIAL_DOWNLOAD_STATE_SUFFIX);
+
+        // first range already on disk, the rest is garbage to be overwritten
+        std::fs::write(
+            &partial_path,
+            format!("{}{}", &content[..10], "x".repeat(20)),
+        )
+        .unwrap();
+        PartialDownload {
+            path: "a/file.txt".to_string(),
+            etag: None,
+            size: content.len() as u64,
+            part_size: 10,
+            completed: BTreeSet::from([0]),
+        }
+        .persist(&state_path)
+        .await
+        .unwrap();
+
+        let options = DownloadOptions {
+            part_size: 10,
+            concurrent: 2,
+            threshold: 0,
+            resume: true,
+        };
+        client
+            .download_file_with("a/file.txt", &dest, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(content, std::fs::read_to_string(&dest).unwrap());
+        assert!(!partial_path.exists());
+        assert!(!state_path.exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_resume_below_threshold() {
+        let client = memory_client();
+        client
+            .inner
+            .write("a/file.txt", "0123456789")
+            .await
+            .unwrap();
+
+        let folder = assert_fs::TempDir::new().unwrap();
+        let dest = folder.path().join("file.txt");
+        let partial_path = with_suffix(&dest, PARTIAL_DOWNLOAD_SUFFIX);
+        let state_path = with_suffix(&dest, PARTIAL_DOWNLOAD_STATE_SUFFIX);
+        std::fs::write(&partial_path, "01234xxxxx").unwrap();
+        PartialDownload {
+            path: "a/file.txt".to_string(),
+            etag: None,
+            size: 10,
+            part_size: 5,
+            completed: BTreeSet::from([0]),
+        }
+        .persist(&state_path)
+        .await
+        .unwrap();
+        assert!(!with_suffix(&state_path, "tmp").exists());
+
+        let options = DownloadOptions {
+            resume: true,
+            ..Default::default()
+        };
+        client
+            .download_file_with("a/file.txt", &dest, &options)
+            .await
+            .unwrap();
+
+        assert_eq!("0123456789", std::fs::read_to_string(&dest).unwrap());
+        assert!(!state_path.exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_refuse_to_resume_a_changed_object() {
+        let client = memory_client();
+        client
+            .inner
+            .write("a/file.txt", "0123456789")
+            .await
+            .unwrap();
+
+        let folder = assert_fs::TempDir::new().unwrap();
+        let dest = folder.path().join("file.txt");
+        std::fs::write(with_suffix(&dest, PARTIAL_DOWNLOAD_SUFFIX), "01234").unwrap();
+        PartialDownload {
+            path: "a/file.txt".to_string(),
+            etag: None,
+            size: 5,
+            part_size: 5,
+            completed: BTreeSet::from([0]),
+        }
+        .persist(&with_suffix(&dest, PARTIAL_DOWNLOAD_STATE_SUFFIX))
+        .await
+        .unwrap();
+
+        let options = DownloadOptions {
+            threshold: 0,
+            resume: true,
+            ..Default::default()
+        };
+        let result = client
+            .download_file_with("a/file.txt", &dest, &options)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(error::Client::DownloadResumeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn should_resolve_upload_target_key() {
+        let key = UploadTarget::Key("releases/v1.2/app.bin".to_string());
+        let prefix = UploadTarget::Prefix("releases/v1.2/".to_string());
+
+        assert_eq!("releases/v1.2/app.bin", key.key("./build/out.bin").unwrap());
+        assert_eq!(
+            "releases/v1.2/out.bin",
+            prefix.key("./build/out.bin").unwrap()
+        );
+        assert!(prefix.key("..").is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn should_upload_directory_tree() {
+        let client = memory_client();
+        let folder = assert_fs::TempDir::new().unwrap();
+        std::fs::create_dir_all(folder.path().join("css/vendor")).unwrap();
+        std::fs::write(folder.path().join("index.html"), "<html/>").unwrap();
+        std::fs::write(folder.path().join("css/vendor/reset.css"), "* {}").unwrap();
+
+        let report = client
+            .upload_dir(folder.path(), "www", &UploadOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(2, report.succeeded().count());
+        assert_eq!(11, report.bytes());
+        assert_eq!(
+            b"* {}".to_vec(),
+            client.download("www/css/vendor/reset.css").await.unwrap()
+        );
+        assert_eq!(
+            b"<html/>".to_vec(),
+            client.download("www/index.html").await.unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_walk_looping_symlinks_once() {
+        let folder = assert_fs::TempDir::new().unwrap();
+        std::fs::create_dir_all(folder.path().join("a/b")).unwrap();
+        std::fs::write(folder.path().join("a/b/c.txt"), "c").unwrap();
+        std::os::unix::fs::symlink(folder.path(), folder.path().join("a/b/loop")).unwrap();
+
+        let files = walk_local(folder.path()).await.unwrap();
+
+        assert_eq!(1, files.len());
+        assert_eq!(folder.path().join("a/b/c.txt"), files[0].0);
+        assert_eq!(1, *files[0].1.as_ref().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn should_report_unreadable_entries_and_keep_uploading() {
+        let client = memory_client();
+        let folder = assert_fs::TempDir::new().unwrap();
+        std::fs::write(folder.path().join("index.html"), "<html/>").unwrap();
+        std::os::unix::fs::symlink(folder.path().join("gone"), folder.path().join("dangling"))
+            .unwrap();
+
+        let report = client
+            .upload_dir(folder.path(), "www", &UploadOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(1, report.succeeded().count());
+        let failed: Vec<_> = report.failed().map(|transfer| &transfer.dest).collect();
+        assert_eq!(vec!["www/dangling"], failed);
+    }
+
+    #[test]
+    fn should_keep_local_paths_below_root() {
+        let root = Path::new("/tmp/rf");
+
+        assert_eq!(Some(root.join("a/b.bin")), local_path(root, "a/b.bin"));
+        assert_eq!(None, local_path(root, "../b.bin"));
+        assert_eq!(None, local_path(root, "/etc/passwd"));
+        assert_eq!(None, local_path(root, ""));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn should_download_prefix_tree() {
+        let client = memory_client();
+        client
+            .inner
+            .create_dir("models/2024-10/empty/")
+            .await
+            .unwrap();
+        client
+            .inner
+            .write("models/2024-10/a.bin", "a")
+            .await
+            .unwrap();
+        client
+            .inner
+            .write("models/2024-10/b/c.bin", "bc")
+            .await
+            .unwrap();
+        client
+            .inner
+            .write("models/2024-11/d.bin", "d")
+            .await
+            .unwrap();
+
+        let folder = assert_fs::TempDir::new().unwrap();
+        let report = client
+            .download_dir("models/2024-10", folder.path(), &DownloadOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(2, report.succeeded().count());
+        assert_eq!(0, report.failed().count());
+        assert_eq!(3, report.bytes());
+        assert_eq!(
+            "bc",
+            std::fs::read_to_string(folder.path().join("b/c.bin")).unwrap()
+        );
+        assert!(!folder.path().join("empty").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_resume_upload_session() {
+        let (multipart, bucket) = crate::multipart::tests::mock_bucket().await;
+        let client = Client {
+            multipart: Some(multipart),
+            ..memory_client()
+        };
+        let content = (0..2 * MIN_PART_SIZE + 10)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let folder = assert_fs::TempDir::new().unwrap();
+        let src = folder.path().join("file.bin");
+        std::fs::write(&src, &content).unwrap();
+
+        let options = UploadOptions {
+            chunk_size: 10,
+            ..Default::default()
+        };
+        let target = UploadTarget::Prefix("a/".to_string());
+        let mut session = client
+            .create_upload_session("memory", src.to_str().unwrap(), &target, &options)
+            .await
+            .unwrap();
+        assert_eq!(MIN_PART_SIZE, session.part_size);
+        assert_eq!(3, session.parts());
+
+        // pretend the first part survived an interrupted run
+        let etag = client.upload_part(&session, 0).await.unwrap();
+        session.completed.insert(0, etag);
+
+        session.check_source().await.unwrap();
+        let changed = UploadSession {
+            modified: UNIX_EPOCH,
+            ..session.clone()
+        };
+        assert!(matches!(
+            changed.check_source().await,
+            Err(error::Client::UploadResumeMismatch(_))
+        ));
+
+        let uploaded = client
+            .upload_session_parts(&session, 2)
+            .try_collect::<BTreeMap<_, _>>()
+            .await
+            .unwrap();
+        assert_eq!(vec![1, 2], uploaded.keys().copied().collect::<Vec<_>>());
+        assert!(bucket.lock().unwrap().objects.is_empty());
+
+        session.completed.extend(uploaded);
+        assert!(session.is_complete());
+        client.complete_upload_session(&session).await.unwrap();
+
+        let stored = bucket.lock().unwrap().objects.remove("root/a/file.bin");
+        assert_eq!(Some(content), stored);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_upload_in_chunks() {
+        let client = memory_client();
+        let content = "all good 👌!".repeat(1024);
+        let options = UploadOptions {
+            chunk_size: 1000,
+            ..Default::default()
+        };
+
+        let (written, _) = client
+            .upload_from(content.as_bytes(), "a/file.txt", &options)
+            .await
+            .unwrap();
+
+        let stored = client.download("a/file.txt").await.unwrap();
+        assert_eq!(content.len() as u64, written);
+        assert_eq!(Ok(content.as_str()), String::from_utf8(stored).as_deref());
+
+        let options = UploadOptions {
+            chunk_size: 0,
+            ..Default::default()
+        };
+        let written = client
+            .write_from("-", content.as_bytes(), 0, "a/empty.txt", &options)
+            .await;
+        assert!(matches!(
+            written,
+            Err(error::Client::UploadInvalidOptions(_))
+        ));
+        assert!(!client.inner.is_exist("a/empty.txt").await.unwrap());
     }
 }
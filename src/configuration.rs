@@ -1,15 +1,17 @@
 use crate::{
     buckets::{GCSBucket, S3Bucket},
-    client::Client,
+    client::{Client, MoveSession, UploadSession},
     error::{self, StoredError},
+    scheduler::TransferLimits,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    io,
     path::{Path, PathBuf},
 };
 use tokio::{
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
     io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
 };
 
@@ -70,6 +72,25 @@ pub enum Bucket {
     S3(S3Bucket),
 }
 
+impl Bucket {
+    pub fn transfer_limits(&self) -> TransferLimits {
+        match self {
+            Bucket::Gcs(gcs) => gcs.transfer,
+            Bucket::S3(s3) => s3.transfer,
+        }
+        .unwrap_or_default()
+    }
+
+    /// Whether uploads create folder markers, off by default
+    pub fn directory_markers(&self) -> bool {
+        match self {
+            Bucket::Gcs(gcs) => gcs.directory_markers,
+            Bucket::S3(s3) => s3.directory_markers,
+        }
+        .unwrap_or_default()
+    }
+}
+
 pub type Configuration = HashMap<String, Bucket>;
 
 pub fn create_client(profile: &str, cfg: &Configuration) -> Result<Option<Client>, error::Client> {
@@ -80,7 +101,11 @@ pub fn create_client(profile: &str, cfg: &Configuration) -> Result<Option<Client
             Bucket::S3(s3) => s3.configuration.clone().try_into()?, // .map_err(|err| ClientError::Initialization(err))?,
         };
 
-        Ok(Some(client))
+        Ok(Some(
+            client
+                .with_limits(bucket.transfer_limits())
+                .with_directory_markers(bucket.directory_markers()),
+        ))
     } else {
         Ok(None)
     }
@@ -106,7 +131,7 @@ where
         &mut self.inner
     }
 
-    pub async fn persist(mut self) -> Result<(), StoredError> {
+    pub async fn persist(&mut self) -> Result<(), StoredError> {
         let content = serde_json::to_string_pretty(&self.inner)?;
         let bytes = content.as_bytes();
 
@@ -157,6 +182,102 @@ impl ConfigurationLayer {
     }
 }
 
+/// Sessions indexed by id, stored one file per session in a folder.
+///
+/// Processes running different sessions never overwrite each other's, and
+/// each file is written aside then renamed into place, so that a crash never
+/// leaves a truncated session behind.
+pub struct Sessions<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    inner: HashMap<String, T>,
+    folder: PathBuf,
+}
+
+impl<T> Sessions<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    /// Loads every session stored in `folder`, creating it if needed
+    async fn load(folder: PathBuf) -> Result<Self, StoredError> {
+        fs::create_dir_all(&folder).await?;
+
+        let mut inner = HashMap::new();
+        let mut entries = fs::read_dir(&folder).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+            else {
+                continue;
+            };
+
+            let content = fs::read_to_string(&path).await?;
+            inner.insert(id.to_string(), serde_json::from_str(&content)?);
+        }
+
+        Ok(Self { inner, folder })
+    }
+
+    pub fn get(&self) -> &HashMap<String, T> {
+        &self.inner
+    }
+
+    /// Records `session` as session `id`, leaving the other sessions alone
+    pub async fn persist(&mut self, id: &str, session: T) -> Result<(), StoredError> {
+        let path = self.folder.join(format!("{id}.json"));
+        let tmp = self.folder.join(format!("{id}.json.tmp"));
+
+        fs::write(&tmp, serde_json::to_string_pretty(&session)?).await?;
+        fs::rename(&tmp, &path).await?;
+        self.inner.insert(id.to_string(), session);
+
+        Ok(())
+    }
+
+    /// Forgets session `id`
+    pub async fn remove(&mut self, id: &str) -> Result<(), StoredError> {
+        match fs::remove_file(self.folder.join(format!("{id}.json"))).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => (),
+        }
+        self.inner.remove(id);
+
+        Ok(())
+    }
+}
+
+/// Pending resumable uploads
+pub type UploadSessionsLayer = Sessions<UploadSession>;
+
+impl UploadSessionsLayer {
+    pub async fn try_init(value: Option<&Path>) -> Result<Self, StoredError> {
+        let folder = match value {
+            Some(folder) => folder.to_path_buf(),
+            None => get_default_folder()?.join("uploads"),
+        };
+
+        Self::load(folder).await
+    }
+}
+
+/// Pending folder moves and bulk renames
+pub type MoveSessionsLayer = Sessions<MoveSession>;
+
+impl MoveSessionsLayer {
+    pub async fn try_init(value: Option<&Path>) -> Result<Self, StoredError> {
+        let folder = match value {
+            Some(folder) => folder.to_path_buf(),
+            None => get_default_folder()?.join("moves"),
+        };
+
+        Self::load(folder).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -244,4 +365,27 @@ mod tests {
         let new_state_layer = PersistenceLayer::try_init(Some(&cfg_path)).await.unwrap();
         assert_eq!(Some("gcs"), new_state_layer.get().current.as_deref());
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn should_persist_sessions_apart() {
+        let dir = TmpDir::create_tmp_dir();
+
+        // two processes loading the sessions before either writes
+        let mut first = Sessions::<String>::load(dir.0.clone()).await.unwrap();
+        let mut second = Sessions::<String>::load(dir.0.clone()).await.unwrap();
+
+        first.persist("a", "first".to_string()).await.unwrap();
+        second.persist("b", "second".to_string()).await.unwrap();
+        first.persist("a", "updated".to_string()).await.unwrap();
+        second.remove("missing").await.unwrap();
+
+        let sessions = Sessions::<String>::load(dir.0.clone()).await.unwrap();
+        assert_eq!(2, sessions.get().len());
+        assert_eq!("updated", sessions.get()["a"]);
+        assert_eq!("second", sessions.get()["b"]);
+
+        first.remove("a").await.unwrap();
+        let sessions = Sessions::<String>::load(dir.0.clone()).await.unwrap();
+        assert_eq!(vec!["b"], sessions.get().keys().collect::<Vec<_>>());
+    }
 }
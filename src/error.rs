@@ -9,12 +9,30 @@ pub enum Client {
     Unhandled(opendal::Error),
     #[error("unknown entry mode for path '{}'", 0)]
     StatUnknownMode(String),
+    #[error("path '{}' does not exist", .0)]
+    StatNotFound(String),
     #[error("path '{}' is not a directory", 0)]
     ListNotDirectory(String),
     #[error("invalid metadata for path '{}'", 0)]
     ListMetadata(String, opendal::Error),
+    #[error("list limit must be greater than 0")]
+    ListInvalidLimit,
+    #[error("invalid filter '{}': {}", .0, .1)]
+    ListInvalidFilter(String, String),
     #[error("cannot download resource: {}", 0)]
     Download(opendal::Error),
+    #[error("cannot stream resource '{}': {}", .0, .1)]
+    DownloadStream(String, io::Error),
+    #[error("cannot write downloaded resource '{}': {}", .0, .1)]
+    DownloadWrite(String, io::Error),
+    #[error("download {} must be greater than 0", .0)]
+    DownloadInvalidOptions(&'static str),
+    #[error("remote path '{}' cannot be mapped to a local path", .0)]
+    DownloadInvalidPath(String),
+    #[error("invalid partial download state '{}': {}", .0, .1)]
+    DownloadState(String, io::Error),
+    #[error("remote object '{}' changed since the partial download was started", .0)]
+    DownloadResumeMismatch(String),
     #[error("invalid path {}", 0)]
     UploadInvalidFilePath(String),
     #[error("cannot find file: {}", 0)]
@@ -23,6 +41,36 @@ pub enum Client {
     UploadLoad(String, io::Error),
     #[error("cannot write to path {}", 0)]
     UploadWrite(String, opendal::Error),
+    #[error("upload {} must be greater than 0", .0)]
+    UploadInvalidOptions(&'static str),
+    #[error("local file '{}' changed since the upload was started", .0)]
+    UploadResumeMismatch(String),
+    #[error("cannot complete the upload of '{}': {}", .0, .1)]
+    UploadComplete(String, opendal::Error),
+    #[error("cannot abort the upload of '{}': {}", .0, .1)]
+    UploadAbort(String, opendal::Error),
+    #[error("the backend has no multipart uploads to resume")]
+    UploadResumeUnsupported,
+    #[error("cannot copy '{}' to '{}': {}", .0, .1, .2)]
+    Copy(String, String, opendal::Error),
+    #[error("'{}' is not an object, folders need a recursive operation", .0)]
+    NotAnObject(String),
+    #[error("cannot move '{}' to '{}': {}", .0, .1, .2)]
+    Rename(String, String, opendal::Error),
+    #[error("cannot move '{}' onto itself", .0)]
+    MoveOntoItself(String),
+    #[error("cannot move folder '{}' to '{}', one contains the other", .0, .1)]
+    MoveOverlap(String, String),
+    #[error("invalid rename pattern '{}': {}", .0, .1)]
+    RenameInvalidPattern(String, String),
+    #[error("copy '{}' does not match its source", .0)]
+    CopyVerify(String),
+    #[error("cannot write inventory: {}", .0)]
+    InventoryWrite(io::Error),
+    #[error("cannot create folder '{}': {}", .0, .1)]
+    Mkdir(String, opendal::Error),
+    #[error("cannot touch '{}': {}", .0, .1)]
+    Touch(String, opendal::Error),
     #[error("cannot delete path {}: {}", path, error)]
     Delete { path: String, error: opendal::Error },
 }
@@ -1,32 +1,69 @@
-use cli::{Args, Commands, Parser, ProfileCommands};
+#![allow(clippy::result_large_err)]
+
+use cli::{Args, Commands, InventoryCommands, Parser, ProfileCommands};
 use colored::{ColoredString, Colorize};
-use futures::StreamExt;
-use opendal::EntryMode;
+use futures::{StreamExt, TryStreamExt};
 use remote_files::{
-    client::StatEntry,
+    client::{
+        Client, DownloadOptions, FindOptions, KeyFilter, ListOptions, MoveSession, ObjectMeta,
+        TransferReport, UploadOptions, UploadTarget,
+    },
     configuration::{
-        self, create_client, Configuration, ConfigurationLayer, Persistence, PersistenceLayer,
-        CONFIGURATION_FILEPATH_ENV_VAR,
+        self, create_client, Configuration, ConfigurationLayer, MoveSessionsLayer, Persistence,
+        PersistenceLayer, UploadSessionsLayer, CONFIGURATION_FILEPATH_ENV_VAR,
+    },
+    error::{self, StoredError},
+    scheduler::TransferLimits,
+    util::{
+        confirm, log_files_table, log_moves_table, log_profiles_table, log_stat_table,
+        log_transfers_table, log_tree, log_upload_sessions_table, log_usage_table, sort_entries,
+        what_next, Column, NextAction, SortBy,
     },
-    error::Client,
-    util::{log_files_table, log_profiles_table, what_next, NextAction},
 };
-use std::{collections::HashMap, io::Write, path::PathBuf, process};
+use std::{
+    collections::HashMap,
+    io::Write,
+    num::{NonZeroU64, NonZeroUsize},
+    path::{Path, PathBuf},
+    process,
+};
 use thiserror::Error;
-use tokio::fs;
+use tokio::{fs, io::BufWriter};
 
 mod cli;
 
 const RF_ICON: &str = "🪣 ";
 
+/// stands for stdin when uploading and for stdout when downloading
+const STDIO_PATH: &str = "-";
+
+/// number of entries fetched at a time when reading a whole listing to sort it
+const SORTED_LIST_PAGE_SIZE: usize = 1000;
+
 #[derive(Debug, Error)]
 enum CliError {
     #[error("{}", 0)]
     Initialization(String),
     #[error("{}", 0)]
     Configuration(String),
+    #[error("{}", .0)]
+    Transfer(String),
+    #[error("{}", .0)]
+    NotFound(String),
     #[error(transparent)]
-    Cli(#[from] Client),
+    Cli(#[from] error::Client),
+}
+
+/// exit code of commands targeting a missing object
+const EXIT_NOT_FOUND: i32 = 2;
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::NotFound(_) => EXIT_NOT_FOUND,
+            _ => 1,
+        }
+    }
 }
 
 async fn set_folder() -> Result<(), CliError> {
@@ -61,14 +98,354 @@ fn get_profile(
         )))
 }
 
+/// Creates the client of `profile`, overriding its parallelism with `jobs` if any
+fn get_client(profile: &str, cfg: &Configuration, jobs: Option<usize>) -> Result<Client, CliError> {
+    let client = create_client(profile, cfg)?
+        .ok_or_else(|| CliError::Initialization(format!("no profile '{profile}' found")))?;
+
+    Ok(match jobs {
+        Some(jobs) => {
+            let limits = TransferLimits {
+                jobs,
+                ..client.limits()
+            };
+            client.with_limits(limits)
+        }
+        None => client,
+    })
+}
+
 fn list_profiles(profiles: Vec<&String>, current: Option<&str>) {
     log_profiles_table(profiles, current);
     println!();
 }
 
-fn list_entries(items: &[StatEntry], should_paginate: bool) {
-    log_files_table(items, true, should_paginate);
+/// Splits a `PROFILE:PATH` argument naming a configured profile, other
+/// arguments being a path of the current profile
+fn profile_path(arg: String, cfg: &Configuration) -> (Option<String>, String) {
+    match arg.split_once(':') {
+        Some((profile, path)) if cfg.contains_key(profile) => {
+            (Some(profile.to_string()), path.to_string())
+        }
+        _ => (None, arg),
+    }
+}
+
+/// Folder paths always end with '/', the root by default
+fn folder_path(path: Option<String>) -> String {
+    let mut path = path.unwrap_or("/".to_string());
+    if !path.ends_with('/') {
+        path.push('/');
+    }
+
+    path
+}
+
+fn list_entries(root: &str, items: &[ObjectMeta], columns: &[Column], should_paginate: bool) {
+    log_files_table(root, items, columns, true, should_paginate);
+    println!();
+}
+
+/// Reports the folder markers that could not be created, which leave the
+/// uploads below them successful
+fn report_marker_errors(errors: &[error::Client]) {
+    for err in errors {
+        error(err.to_string());
+    }
+}
+
+fn report_transfers(report: &TransferReport) -> Result<(), CliError> {
+    log_transfers_table(report);
     println!();
+    report_marker_errors(&report.marker_errors);
+
+    let failed = report.failed().count();
+    ok(format!(
+        "transferred {} files ({} bytes), {} failed\n",
+        report.succeeded().count(),
+        report.bytes(),
+        failed
+    ));
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(CliError::Transfer(format!("{failed} transfers failed"))),
+    }
+}
+
+/// cp-like semantics: a trailing '/' uploads into a folder, anything else is the full key
+fn upload_target(dest: String) -> UploadTarget {
+    match dest.as_bytes() {
+        &[.., b'/'] => UploadTarget::Prefix(dest),
+        _ => UploadTarget::Key(dest),
+    }
+}
+
+async fn upload_resumable(
+    client: &Client,
+    profile: &str,
+    src: &str,
+    target: &UploadTarget,
+    options: &UploadOptions,
+    resume: bool,
+) -> Result<(), CliError> {
+    let persist_err =
+        |err: StoredError| CliError::Configuration(format!("cannot persist uploads: {}", err));
+    let mut sessions_layer = UploadSessionsLayer::try_init(None)
+        .await
+        .map_err(|err| CliError::Configuration(format!("cannot load uploads: {}", err)))?;
+
+    let mut pending = None;
+    for session in sessions_layer.get().values() {
+        if session.matches(profile, src, target).await {
+            pending = Some(session.clone());
+            break;
+        }
+    }
+
+    let mut session = match (pending, resume) {
+        (Some(session), true) => {
+            session.check_source().await?;
+            ok(format!(
+                "resuming upload '{}' from part {}/{}\n",
+                session.id,
+                session.completed.len() + 1,
+                session.parts()
+            ));
+            session
+        }
+        (Some(session), false) => {
+            return Err(CliError::Configuration(format!(
+                "upload '{}' is pending: use '--resume' or '--abort {}'",
+                session.id, session.id
+            )))
+        }
+        (None, true) => {
+            return Err(CliError::Configuration(format!(
+                "no pending upload of '{src}' to '{}'",
+                target.key(src)?
+            )))
+        }
+        (None, false) => {
+            client
+                .create_upload_session(profile, src, target, options)
+                .await?
+        }
+    };
+
+    sessions_layer
+        .persist(&session.id, session.clone())
+        .await
+        .map_err(persist_err)?;
+
+    let mut parts = client.upload_session_parts(&session, options.concurrent);
+    while let Some((index, etag)) = parts.try_next().await? {
+        session.completed.insert(index, etag);
+        sessions_layer
+            .persist(&session.id, session.clone())
+            .await
+            .map_err(persist_err)?;
+    }
+    drop(parts);
+
+    let marker_errors = client.complete_upload_session(&session).await?;
+    report_marker_errors(&marker_errors);
+
+    sessions_layer
+        .remove(&session.id)
+        .await
+        .map_err(persist_err)
+}
+
+/// Moves every object below folder `src` into folder `dest`, first copying
+/// all of them then deleting the sources, and records progress so that the
+/// same command resumes an interrupted move
+async fn move_folder(
+    client: &Client,
+    profile: &str,
+    src: &str,
+    dest: &str,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let sessions_layer = load_move_sessions().await?;
+
+    let pending = sessions_layer
+        .get()
+        .values()
+        .find(|session| session.matches(profile, src, dest))
+        .cloned();
+    let session = match pending {
+        Some(session) => {
+            ok(format!(
+                "resuming move '{}': {}/{} objects copied, {} deleted\n",
+                session.id,
+                session.copied.len(),
+                session.objects.len(),
+                session.deleted.len()
+            ));
+            session
+        }
+        None => client.plan_move(profile, src, dest).await?,
+    };
+
+    if dry_run {
+        let moves = pending_moves(&session);
+        log_moves_table(&moves, false);
+        println!();
+        ok(format!("{} objects would be moved\n", moves.len()));
+
+        return Ok(());
+    }
+
+    run_move(client, sessions_layer, session).await
+}
+
+/// Renames the objects below folder `prefix` whose key matches `from` with
+/// template `to`, once the plan is free of collisions and confirmed
+async fn rename_many(
+    client: &Client,
+    profile: &str,
+    prefix: &str,
+    from: &str,
+    to: &str,
+    yes: bool,
+    dry_run: bool,
+) -> Result<(), CliError> {
+    let sessions_layer = load_move_sessions().await?;
+
+    let pending = sessions_layer
+        .get()
+        .values()
+        .find(|session| session.matches_rename(profile, prefix, from, to))
+        .cloned();
+    let session = match pending {
+        Some(session) => {
+            ok(format!(
+                "resuming rename '{}': {}/{} objects copied, {} deleted\n",
+                session.id,
+                session.copied.len(),
+                session.objects.len(),
+                session.deleted.len()
+            ));
+            session
+        }
+        None => {
+            let (session, collisions) = client.plan_rename(profile, prefix, from, to).await?;
+            if !collisions.is_empty() {
+                for collision in &collisions {
+                    let srcs = collision
+                        .srcs
+                        .iter()
+                        .map(|src| format!("'{src}'"))
+                        .collect::<Vec<_>>();
+                    error(format!(
+                        "'{}' is claimed by {}\n",
+                        collision.dest,
+                        srcs.join(", ")
+                    ));
+                }
+
+                return Err(CliError::Transfer(format!(
+                    "{} destinations collide, nothing renamed",
+                    collisions.len()
+                )));
+            }
+            session
+        }
+    };
+
+    let renames = pending_moves(&session);
+    log_moves_table(&renames, false);
+    println!();
+
+    if dry_run || renames.is_empty() {
+        ok(format!("{} objects would be renamed\n", renames.len()));
+
+        return Ok(());
+    }
+
+    if !yes && !confirm(&format!("rename {} objects?", renames.len())) {
+        ok("nothing renamed\n");
+
+        return Ok(());
+    }
+
+    run_move(client, sessions_layer, session).await
+}
+
+async fn load_move_sessions() -> Result<MoveSessionsLayer, CliError> {
+    MoveSessionsLayer::try_init(None)
+        .await
+        .map_err(|err| CliError::Configuration(format!("cannot load moves: {}", err)))
+}
+
+/// `(source, destination, size)` of the objects of `session` not moved yet
+fn pending_moves(session: &MoveSession) -> Vec<(String, String, u64)> {
+    session
+        .objects
+        .iter()
+        .filter(|(key, _)| !session.deleted.contains(*key))
+        .map(|(key, size)| (key.clone(), session.dest_key(key), *size))
+        .collect()
+}
+
+/// Runs `session`, persisting its progress after every object
+async fn run_move(
+    client: &Client,
+    mut sessions_layer: MoveSessionsLayer,
+    mut session: MoveSession,
+) -> Result<(), CliError> {
+    let persist_err =
+        |err: StoredError| CliError::Configuration(format!("cannot persist moves: {}", err));
+
+    sessions_layer
+        .persist(&session.id, session.clone())
+        .await
+        .map_err(persist_err)?;
+
+    let mut report = TransferReport::default();
+    let mut copies = client.move_session_copies(&session);
+    while let Some(transfer) = copies.next().await {
+        if transfer.result.is_ok() {
+            session.copied.insert(transfer.src.clone());
+            sessions_layer
+                .persist(&session.id, session.clone())
+                .await
+                .map_err(persist_err)?;
+        }
+        report.transfers.push(transfer);
+    }
+    drop(copies);
+
+    // sources are only deleted once every object is safely copied
+    if report.failed().count() > 0 {
+        return report_transfers(&report);
+    }
+
+    let mut deletes = client.move_session_deletes(&session);
+    while let Some(transfer) = deletes.next().await {
+        match transfer.result {
+            Ok(_) => {
+                session.deleted.insert(transfer.src.clone());
+                sessions_layer
+                    .persist(&session.id, session.clone())
+                    .await
+                    .map_err(persist_err)?;
+            }
+            // a source left behind fails the move, re-running it deletes it
+            Err(_) => report.transfers.push(transfer),
+        }
+    }
+    drop(deletes);
+
+    if session.is_complete() {
+        sessions_layer
+            .remove(&session.id)
+            .await
+            .map_err(persist_err)?;
+    }
+
+    report_transfers(&report)
 }
 
 enum Level {
@@ -100,7 +477,7 @@ fn ok(text: impl AsRef<str>) {
 }
 
 fn error(text: impl AsRef<str>) {
-    println!("[{}]: {}", Level::Error.into_str(), text.as_ref());
+    eprintln!("[{}]: {}", Level::Error.into_str(), text.as_ref());
 }
 
 async fn run() -> Result<(), CliError> {
@@ -209,18 +586,22 @@ async fn run() -> Result<(), CliError> {
                 }
             }
         },
-        Commands::List { path, paginate } => {
+        Commands::List {
+            path,
+            paginate,
+            start_after,
+            recursive,
+            glob,
+            regex,
+            sort,
+            reverse,
+            dirs_first,
+            columns,
+        } => {
             welcome();
 
-            let mut path = path.unwrap_or("/".to_string());
+            let path = folder_path(path);
             let profile = get_profile(args.profile, pers, cfg)?;
-            let path = match path.as_bytes() {
-                &[.., b'/'] => path,
-                _ => {
-                    path.push('/');
-                    path
-                }
-            };
 
             ok(format!(
                 "listing content of folder '{}' for profile '{}'\n",
@@ -229,9 +610,41 @@ async fn run() -> Result<(), CliError> {
             ));
 
             let mut page_count = 0;
-            let client = create_client(&profile, cfg)?.unwrap();
+            let client = get_client(&profile, cfg, args.jobs)?;
             let should_paginate = paginate.is_some();
-            let mut stream = client.list(&path, paginate).await?;
+            // without pagination, sorting spans the whole listing
+            let should_sort_all = !should_paginate && (sort.is_some() || dirs_first);
+            let filter = match (glob, regex) {
+                (Some(glob), _) => Some(KeyFilter::glob(&glob)?),
+                (_, Some(regex)) => Some(KeyFilter::regex(&regex)?),
+                _ => None,
+            };
+            let options = ListOptions {
+                limit: match paginate {
+                    Some(paginate) => Some(paginate.get()),
+                    None => should_sort_all.then_some(SORTED_LIST_PAGE_SIZE),
+                },
+                start_after,
+                recursive,
+                filter,
+            };
+            let mut stream = client.list_with(&path, &options).await?;
+
+            if should_sort_all {
+                let mut items = (&mut stream)
+                    .map_ok(|page| page.entries)
+                    .try_concat()
+                    .await?;
+                sort_entries(
+                    &mut items,
+                    sort.unwrap_or(SortBy::Name),
+                    reverse,
+                    dirs_first,
+                );
+                list_entries(&path, &items, &columns, should_paginate);
+
+                return Ok(());
+            }
 
             while let Some(page) = stream.next().await {
                 page_count += 1;
@@ -241,33 +654,49 @@ async fn run() -> Result<(), CliError> {
                     &page_count.to_string().bold().cyan()
                 ));
 
-                let items = page.await;
+                let page = page?;
+                let mut items = page.entries;
+                if sort.is_some() || dirs_first {
+                    sort_entries(
+                        &mut items,
+                        sort.unwrap_or(SortBy::Name),
+                        reverse,
+                        dirs_first,
+                    );
+                }
 
-                list_entries(items.as_ref(), should_paginate);
+                list_entries(&path, items.as_ref(), &columns, should_paginate);
 
                 if should_paginate {
                     print!("press 'q' to quit, type an integer to download a file, or anything else to keep scrolling 👀 :");
                     std::io::stdout().flush().unwrap();
 
                     let item_to_download = match what_next().await {
-                        NextAction::Quit => break,
+                        NextAction::Quit => {
+                            if let Some(cursor) = page.cursor {
+                                println!();
+                                ok(format!(
+                                    "use '--start-after {}' to resume listing from here\n",
+                                    cursor.bold().green()
+                                ));
+                            }
+                            break;
+                        }
                         NextAction::Next => continue,
                         NextAction::Print(idx) => items.get(idx - 1),
                     };
 
-                    if let Some((name, _, _, mode)) = item_to_download {
-                        if mode != &EntryMode::FILE {
+                    if let Some(item) = item_to_download {
+                        if !item.is_file() {
                             error("download is available for files only\n");
                         } else {
-                            let filepath = PathBuf::from(&path).join(name);
-                            let filepath = filepath.to_str().unwrap();
-                            let content = client.download(filepath).await?;
-
+                            let filepath = item.path.as_str();
                             println!();
                             ok(format!("printing '{filepath}'\n"));
 
-                            std::io::stdout().write_all(&content).unwrap();
-                            std::io::stdout().flush().unwrap();
+                            client
+                                .download_to(filepath, &mut tokio::io::stdout())
+                                .await?;
 
                             println!();
                             ok("=== EOF ===\n");
@@ -280,47 +709,528 @@ async fn run() -> Result<(), CliError> {
                 println!()
             }
         }
-        Commands::Delete { path } => {
+        Commands::Tree { path, max_depth } => {
             welcome();
 
+            let path = folder_path(path);
             let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
 
             ok(format!(
-                "deleting file '{}' for profile '{}'\n",
-                path,
+                "walking folder '{}' for profile '{}'\n",
+                path.as_str().bold().green(),
                 profile.bold().cyan()
             ));
 
-            let client = create_client(&profile, cfg)?.unwrap();
-            client.delete(&path).await?;
+            let entries = client
+                .walk(&path, max_depth)
+                .await?
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            log_tree(&path, &entries, max_depth, false);
+            println!();
+        }
+        Commands::Stat { path, json, raw } => {
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            let meta = match client.stat(&path).await {
+                Err(err @ error::Client::StatNotFound(_)) => {
+                    return Err(CliError::NotFound(err.to_string()))
+                }
+                meta => meta?,
+            };
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&meta).unwrap());
+            } else {
+                welcome();
+                log_stat_table(&meta, raw);
+                println!();
+            }
         }
-        Commands::Upload { src, mut dest } => {
+        Commands::Inventory {
+            command:
+                InventoryCommands::Export {
+                    prefix,
+                    format,
+                    output,
+                },
+        } => {
+            let prefix = folder_path(prefix);
+            let format = format.into();
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            if output == STDIO_PATH {
+                client
+                    .export_inventory(&prefix, format, &mut tokio::io::stdout())
+                    .await?;
+                return Ok(());
+            }
+
             welcome();
 
+            ok(format!(
+                "exporting inventory of folder '{}' to '{}' for profile '{}'\n",
+                prefix.as_str().bold().green(),
+                output,
+                profile.bold().cyan()
+            ));
+
+            let file = fs::File::create(&output)
+                .await
+                .map_err(error::Client::InventoryWrite)?;
+            let count = client
+                .export_inventory(&prefix, format, &mut BufWriter::new(file))
+                .await?;
+
+            ok(format!("exported {count} objects\n"));
+        }
+        Commands::Find {
+            path,
+            larger_than,
+            older_than,
+            content_type,
+            json,
+            delete,
+            yes,
+            download_to,
+        } => {
+            let path = folder_path(path);
             let profile = get_profile(args.profile, pers, cfg)?;
-            let dest = match dest.as_bytes() {
-                &[.., b'/'] => dest,
-                _ => {
-                    dest.push('/');
-                    dest
+            let client = get_client(&profile, cfg, args.jobs)?;
+            let options = FindOptions {
+                larger_than,
+                older_than,
+                content_type,
+            };
+
+            // keys go to stdout as they are found, so that they can be piped
+            let mut found = vec![];
+            let mut objects = client.find(&path, &options).await?;
+            while let Some(object) = objects.try_next().await? {
+                if json {
+                    println!("{}", serde_json::to_string(&object).unwrap());
+                } else {
+                    println!("{}", object.path);
+                }
+                found.push((object.path, object.content_length.unwrap_or_default()));
+            }
+
+            if delete {
+                welcome();
+
+                if !yes && !confirm(&format!("delete {} objects?", found.len())) {
+                    ok("nothing deleted\n");
+
+                    return Ok(());
+                }
+
+                ok(format!(
+                    "deleting {} objects for profile '{}'\n",
+                    found.len(),
+                    profile.bold().cyan()
+                ));
+
+                let keys = found.into_iter().map(|(key, _)| key).collect();
+                return report_transfers(&client.delete_objects(keys).await);
+            }
+
+            if let Some(dest) = download_to {
+                welcome();
+                ok(format!(
+                    "downloading {} objects to '{}' for profile '{}'\n",
+                    found.len(),
+                    dest.display(),
+                    profile.bold().cyan()
+                ));
+
+                let report = client
+                    .download_many(&path, found, &dest, &DownloadOptions::default())
+                    .await;
+                return report_transfers(&report);
+            }
+        }
+        Commands::Du {
+            path,
+            depth,
+            top,
+            raw,
+        } => {
+            welcome();
+
+            let path = folder_path(path);
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            ok(format!(
+                "summing usage of folder '{}' for profile '{}'\n",
+                path.as_str().bold().green(),
+                profile.bold().cyan()
+            ));
+
+            let mut usages = client.disk_usage(&path, depth).await?;
+            if let Some(top) = top {
+                // the listed folder stays first, with the totals
+                usages.truncate(top.saturating_add(1));
+            }
+
+            log_usage_table(&usages, raw);
+            println!();
+        }
+        Commands::Cp {
+            src,
+            dest,
+            recursive,
+        } => {
+            welcome();
+
+            let (src_profile, src) = profile_path(src, cfg);
+            let (dest_profile, dest) = profile_path(dest, cfg);
+            let (src_profile, dest_profile) = match (src_profile, dest_profile) {
+                (Some(src_profile), Some(dest_profile)) => (src_profile, dest_profile),
+                (src_profile, dest_profile) => {
+                    let profile = get_profile(args.profile, pers, cfg)?;
+                    (
+                        src_profile.unwrap_or_else(|| profile.clone()),
+                        dest_profile.unwrap_or(profile),
+                    )
                 }
             };
 
+            let client = get_client(&src_profile, cfg, args.jobs)?;
+
+            if src_profile != dest_profile {
+                let target = get_client(&dest_profile, cfg, args.jobs)?;
+
+                if recursive {
+                    ok(format!(
+                        "copying folder '{}' of profile '{}' to '{}' of profile '{}'\n",
+                        src,
+                        src_profile.bold().cyan(),
+                        dest.as_str().bold().green(),
+                        dest_profile.bold().cyan()
+                    ));
+
+                    let report = client.copy_dir_to(&src, &target, &dest).await?;
+
+                    return report_transfers(&report);
+                }
+
+                let dest = upload_target(dest).key(&src)?;
+
+                ok(format!(
+                    "copying '{}' of profile '{}' to '{}' of profile '{}'\n",
+                    src,
+                    src_profile.bold().cyan(),
+                    dest.as_str().bold().green(),
+                    dest_profile.bold().cyan()
+                ));
+
+                client.copy_to(&src, &target, &dest).await?;
+
+                return Ok(());
+            }
+
+            let profile = src_profile;
+
+            if recursive {
+                ok(format!(
+                    "copying folder '{}' to '{}' for profile '{}'\n",
+                    src,
+                    dest.as_str().bold().green(),
+                    profile.bold().cyan()
+                ));
+
+                let report = client.copy_dir(&src, &dest).await?;
+
+                return report_transfers(&report);
+            }
+
+            // same cp-like semantics as uploads
+            let dest = upload_target(dest).key(&src)?;
+
+            ok(format!(
+                "copying '{}' to '{}' for profile '{}'\n",
+                src,
+                dest.as_str().bold().green(),
+                profile.bold().cyan()
+            ));
+
+            client.copy(&src, &dest).await?;
+        }
+        Commands::Mv {
+            src,
+            dest,
+            recursive,
+            dry_run,
+        } => {
+            welcome();
+
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            if recursive {
+                ok(format!(
+                    "moving folder '{}' to '{}' for profile '{}'\n",
+                    src,
+                    dest.as_str().bold().green(),
+                    profile.bold().cyan()
+                ));
+
+                return move_folder(&client, &profile, &src, &dest, dry_run).await;
+            }
+
+            // same cp-like semantics as uploads
+            let dest = upload_target(dest).key(&src)?;
+            if src == dest {
+                return Err(error::Client::MoveOntoItself(src).into());
+            }
+
+            if dry_run {
+                let meta = client.stat(&src).await?;
+                if !meta.is_file() {
+                    return Err(error::Client::NotAnObject(src).into());
+                }
+
+                let size = meta.content_length.unwrap_or_default();
+                log_moves_table(&[(src, dest, size)], false);
+                println!();
+
+                return Ok(());
+            }
+
             ok(format!(
-                "uploading file '{}' to folder '{}' for profile '{}'\n",
+                "moving '{}' to '{}' for profile '{}'\n",
                 src,
                 dest.as_str().bold().green(),
                 profile.bold().cyan()
             ));
 
-            let client = create_client(&profile, cfg)?.unwrap();
+            client.rename(&src, &dest).await?;
+        }
+        Commands::RenameMany {
+            prefix,
+            from,
+            to,
+            yes,
+            dry_run,
+        } => {
+            welcome();
+
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            ok(format!(
+                "renaming keys matching '{}' below '{}' to '{}' for profile '{}'\n",
+                from,
+                prefix,
+                to.as_str().bold().green(),
+                profile.bold().cyan()
+            ));
+
+            return rename_many(&client, &profile, &prefix, &from, &to, yes, dry_run).await;
+        }
+        Commands::Mkdir { path } => {
+            welcome();
+
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            ok(format!(
+                "creating folder '{}' for profile '{}'\n",
+                path.as_str().bold().green(),
+                profile.bold().cyan()
+            ));
+
+            client.mkdir(&path).await?;
+        }
+        Commands::Touch { path } => {
+            welcome();
+
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            ok(format!(
+                "touching '{}' for profile '{}'\n",
+                path.as_str().bold().green(),
+                profile.bold().cyan()
+            ));
+
+            let marker_errors = client.touch(&path).await?;
+            report_marker_errors(&marker_errors);
+        }
+        Commands::Delete { path } => {
+            welcome();
+
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            ok(format!(
+                "deleting file '{}' for profile '{}'\n",
+                path,
+                profile.bold().cyan()
+            ));
+
+            client.delete(&path).await?;
+        }
+        Commands::Upload {
+            list_pending: true, ..
+        } => {
+            welcome();
+
+            let sessions_layer = UploadSessionsLayer::try_init(None)
+                .await
+                .map_err(|err| CliError::Configuration(format!("cannot load uploads: {}", err)))?;
+
+            ok("here's the list of pending uploads\n");
+            log_upload_sessions_table(sessions_layer.get().values().collect());
+            println!();
+            ok("use 'upload --resume' to continue or 'upload --abort' to discard them\n");
+        }
+        Commands::Upload {
+            abort: Some(id), ..
+        } => {
+            welcome();
+
+            let mut sessions_layer = UploadSessionsLayer::try_init(None)
+                .await
+                .map_err(|err| CliError::Configuration(format!("cannot load uploads: {}", err)))?;
+            let session = sessions_layer.get().get(&id).cloned().ok_or_else(|| {
+                CliError::Configuration(format!("no pending upload '{id}' found"))
+            })?;
+
+            ok(format!(
+                "aborting upload of '{}' for profile '{}'\n",
+                session.dest.as_str().bold().green(),
+                session.profile.bold().cyan()
+            ));
+
+            let client = get_client(&session.profile, cfg, args.jobs)?;
+            client.abort_upload_session(&session).await?;
 
-            client.upload(&src, &dest, None).await?;
+            sessions_layer.remove(&id).await.map_err(|err| {
+                CliError::Configuration(format!("cannot persist uploads: {}", err))
+            })?;
         }
-        Commands::Download { src, dest } => {
+        Commands::Upload {
+            src,
+            dest,
+            chunk_size,
+            concurrent,
+            recursive,
+            resumable,
+            resume,
+            ..
+        } => {
+            // SAFETY: required by clap unless listing or aborting
+            let src = src.unwrap();
+            let dest = dest.unwrap();
+
+            let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            let defaults = UploadOptions::default();
+            let options = UploadOptions {
+                chunk_size: chunk_size.map_or(defaults.chunk_size, NonZeroUsize::get),
+                concurrent: concurrent.map_or(defaults.concurrent, NonZeroUsize::get),
+                ..defaults
+            };
+
+            if src == STDIO_PATH {
+                if resumable || resume {
+                    return Err(CliError::Configuration(
+                        "resumable uploads require a local file".to_string(),
+                    ));
+                }
+
+                let UploadTarget::Key(dest) = upload_target(dest) else {
+                    return Err(CliError::Configuration(
+                        "a full key is required when reading from stdin".to_string(),
+                    ));
+                };
+
+                let (_, marker_errors) = client
+                    .upload_from(tokio::io::stdin(), &dest, &options)
+                    .await?;
+                report_marker_errors(&marker_errors);
+                return Ok(());
+            }
+
             welcome();
 
+            if recursive {
+                ok(format!(
+                    "uploading directory '{}' to '{}' for profile '{}'\n",
+                    src,
+                    dest.as_str().bold().green(),
+                    profile.bold().cyan()
+                ));
+
+                let report = client.upload_dir(Path::new(&src), &dest, &options).await?;
+
+                return report_transfers(&report);
+            }
+
+            let target = upload_target(dest);
+
+            ok(format!(
+                "uploading file '{}' to '{}' for profile '{}'\n",
+                src,
+                target.key(&src)?.as_str().bold().green(),
+                profile.bold().cyan()
+            ));
+
+            if resumable || resume {
+                upload_resumable(&client, &profile, &src, &target, &options, resume).await?;
+            } else {
+                let marker_errors = client.upload_with(&src, &target, &options).await?;
+                report_marker_errors(&marker_errors);
+            }
+        }
+        Commands::Download {
+            src,
+            dest,
+            part_size,
+            concurrent,
+            threshold,
+            resume,
+            recursive,
+        } => {
             let profile = get_profile(args.profile, pers, cfg)?;
+            let client = get_client(&profile, cfg, args.jobs)?;
+
+            if dest == STDIO_PATH {
+                client.download_to(&src, &mut tokio::io::stdout()).await?;
+                return Ok(());
+            }
+
+            welcome();
+
+            let defaults = DownloadOptions::default();
+            let options = DownloadOptions {
+                part_size: part_size.map_or(defaults.part_size, NonZeroU64::get),
+                concurrent: concurrent.map_or(defaults.concurrent, NonZeroUsize::get),
+                threshold: threshold.unwrap_or(defaults.threshold),
+                resume,
+            };
+
+            if recursive {
+                ok(format!(
+                    "downloading folder '{}' to '{}' for profile '{}'\n",
+                    src,
+                    dest.as_str().bold().green(),
+                    profile.bold().cyan()
+                ));
+
+                let report = client
+                    .download_dir(&src, Path::new(&dest), &options)
+                    .await?;
+
+                return report_transfers(&report);
+            }
 
             ok(format!(
                 "downloading file '{}' to '{}' for profile '{}'\n",
@@ -329,10 +1239,7 @@ async fn run() -> Result<(), CliError> {
                 profile.bold().cyan()
             ));
 
-            let client = create_client(&profile, cfg)?.unwrap();
-
-            let contents = client.download(&src).await?;
-            fs::write(dest, contents).await.unwrap();
+            client.download_file_with(&src, &dest, &options).await?;
         }
     };
 
@@ -343,6 +1250,6 @@ async fn run() -> Result<(), CliError> {
 async fn main() {
     if let Err(err) = run().await {
         error(format!("{:#?}\n", err));
-        process::exit(1);
+        process::exit(err.exit_code());
     }
 }
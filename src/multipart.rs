@@ -0,0 +1,730 @@
+use crate::buckets::{GCSConfig, S3Config};
+use opendal::{Error, ErrorKind, Result};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqsign::{
+    AwsConfig, AwsDefaultLoader, AwsV4Signer, GoogleCredentialLoader, GoogleSigner,
+    GoogleTokenLoader,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, ETAG, HOST},
+    Method, Request, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// smallest part of a multipart upload, but the last one
+pub const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// largest number of parts of a multipart upload
+pub const MAX_PARTS: u64 = 10_000;
+
+const DEFAULT_S3_ENDPOINT: &str = "https://s3.amazonaws.com";
+
+const DEFAULT_GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+const GCS_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// characters escaped in the keys of request urls, as opendal does
+const KEY_ESCAPES: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+enum Auth {
+    Aws(AwsDefaultLoader, AwsV4Signer),
+    Google(GoogleTokenLoader, GoogleSigner),
+}
+
+/// Multipart uploads through the XML API shared by S3 and GCS.
+///
+/// opendal drives multipart uploads itself without exposing their ids, so an
+/// upload it started cannot be continued by another process. Uploads started
+/// here are identified by an id that outlives this process, and their parts
+/// stay out of listings until the upload is completed.
+///
+/// The same API lists the storage classes of objects, which opendal
+/// metadata lacks.
+#[derive(Clone)]
+pub struct Multipart {
+    http: reqwest::Client,
+    /// url of the bucket, path-style
+    bucket: String,
+    /// key prefix of the profile, without surrounding slashes
+    root: String,
+    /// headers of the requests starting an upload, e.g. its storage class
+    headers: HeaderMap,
+    auth: Arc<Auth>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InitiateMultipartUploadResult {
+    upload_id: String,
+}
+
+/// A page of a `ListObjectsV2` listing
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBucketResult {
+    #[serde(default)]
+    contents: Vec<ListedObject>,
+    next_continuation_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListedObject {
+    key: String,
+    storage_class: Option<String>,
+}
+
+/// Keys and storage classes of a page of objects, with the token of the next
+/// page if any
+pub type StorageClassPage = (Vec<(String, String)>, Option<String>);
+
+#[derive(Serialize)]
+#[serde(rename = "CompleteMultipartUpload")]
+struct CompleteMultipartUpload {
+    #[serde(rename = "Part")]
+    parts: Vec<CompletedPart>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct CompletedPart {
+    part_number: u64,
+    #[serde(rename = "ETag")]
+    etag: String,
+}
+
+impl Multipart {
+    /// Starts the upload of object `key` and returns its id
+    pub async fn create(&self, key: &str, content_type: Option<&str>) -> Result<String> {
+        let mut headers = self.headers.clone();
+        if let Some(content_type) = content_type {
+            headers.insert(CONTENT_TYPE, header_value(content_type)?);
+        }
+
+        let response = self
+            .send(Method::POST, key, "uploads", headers, vec![])
+            .await?;
+        let body = response.bytes().await.map_err(request_error)?;
+        let result: InitiateMultipartUploadResult =
+            quick_xml::de::from_reader(body.as_ref()).map_err(response_error)?;
+
+        Ok(result.upload_id)
+    }
+
+    /// Uploads part `index`, counted from 0, of upload `upload_id` of object
+    /// `key` and returns its ETag
+    pub async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &str,
+        index: u64,
+        body: Vec<u8>,
+    ) -> Result<String> {
+        let query = format!("partNumber={}&uploadId={}", index + 1, encode(upload_id));
+        let response = self
+            .send(Method::PUT, key, &query, HeaderMap::new(), body)
+            .await?;
+
+        response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(String::from)
+            .ok_or_else(|| Error::new(ErrorKind::Unexpected, "uploaded part has no ETag"))
+    }
+
+    /// Assembles the `parts` of upload `upload_id` into object `key`, given
+    /// their ETag by index
+    pub async fn complete(
+        &self,
+        key: &str,
+        upload_id: &str,
+        parts: &BTreeMap<u64, String>,
+    ) -> Result<()> {
+        let body = complete_body(parts)?;
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/xml"));
+
+        let query = format!("uploadId={}", encode(upload_id));
+        let response = self
+            .send(Method::POST, key, &query, headers, body.into_bytes())
+            .await?;
+
+        // S3 may report a failed completion within a successful response
+        let body = response.text().await.map_err(request_error)?;
+        if body.contains("<Error>") {
+            return Err(Error::new(ErrorKind::Unexpected, "cannot complete upload")
+                .with_context("response", body));
+        }
+
+        Ok(())
+    }
+
+    /// Discards upload `upload_id` of object `key` and its parts
+    pub async fn abort(&self, key: &str, upload_id: &str) -> Result<()> {
+        let query = format!("uploadId={}", encode(upload_id));
+
+        match self
+            .send(Method::DELETE, key, &query, HeaderMap::new(), vec![])
+            .await
+        {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lists the storage classes of the objects below key prefix `prefix`,
+    /// in key order, one page at a time from continuation token `token`.
+    ///
+    /// Keys are relative to the profile prefix, as opendal paths are.
+    pub async fn storage_classes(
+        &self,
+        prefix: &str,
+        token: Option<&str>,
+    ) -> Result<StorageClassPage> {
+        let mut query = format!("list-type=2&prefix={}", encode(&self.path(prefix)));
+        if let Some(token) = token {
+            query.push_str(&format!("&continuation-token={}", encode(token)));
+        }
+
+        let url = format!("{}/?{query}", self.bucket);
+        let response = self
+            .execute(Method::GET, url, HeaderMap::new(), vec![])
+            .await?;
+        let body = response.bytes().await.map_err(request_error)?;
+        let result: ListBucketResult =
+            quick_xml::de::from_reader(body.as_ref()).map_err(response_error)?;
+
+        let root = match self.root.as_str() {
+            "" => String::new(),
+            root => format!("{root}/"),
+        };
+        let classes = result
+            .contents
+            .into_iter()
+            .filter_map(|object| {
+                let key = object.key.strip_prefix(root.as_str())?.to_string();
+
+                Some((key, object.storage_class?))
+            })
+            .collect();
+
+        Ok((classes, result.next_continuation_token))
+    }
+
+    /// Sends a signed request on object `key`, failing on error statuses
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<Response> {
+        let url = format!("{}/{}?{query}", self.bucket, encode(&self.path(key)));
+
+        self.execute(method, url, headers, body).await
+    }
+
+    /// Sends a signed request to `url`, failing on error statuses
+    async fn execute(
+        &self,
+        method: Method,
+        url: String,
+        mut headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<Response> {
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+
+        let mut request = self
+            .http
+            .request(method, url)
+            .headers(headers)
+            .body(body)
+            .build()
+            .map_err(request_error)?;
+        self.sign(&mut request).await?;
+
+        let response = self.http.execute(request).await.map_err(request_error)?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let kind = match status {
+            StatusCode::NOT_FOUND => ErrorKind::NotFound,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Unexpected,
+        };
+        let body = response.text().await.unwrap_or_default();
+
+        Err(
+            Error::new(kind, format!("request failed with status {status}"))
+                .with_context("response", body),
+        )
+    }
+
+    /// Signs `request` with the credentials found, if any
+    async fn sign(&self, request: &mut Request) -> Result<()> {
+        let signed = match self.auth.as_ref() {
+            Auth::Aws(loader, signer) => match loader.load().await.map_err(sign_error)? {
+                Some(credential) => signer.sign(request, &credential),
+                None => Ok(()),
+            },
+            Auth::Google(loader, signer) => match loader.load().await.map_err(sign_error)? {
+                Some(token) => signer.sign(request, &token),
+                None => Ok(()),
+            },
+        };
+        signed.map_err(sign_error)?;
+
+        // the http client sets it, as opendal does
+        request.headers_mut().remove(HOST);
+
+        Ok(())
+    }
+
+    /// Full key of `key` in the bucket
+    fn path(&self, key: &str) -> String {
+        let key = key.trim_start_matches('/');
+
+        match self.root.as_str() {
+            "" => key.to_string(),
+            root => format!("{root}/{key}"),
+        }
+    }
+}
+
+impl TryFrom<&S3Config> for Multipart {
+    type Error = Error;
+
+    fn try_from(config: &S3Config) -> Result<Self> {
+        let mut aws = AwsConfig::default().from_profile().from_env();
+        if let Some(region) = &config.region {
+            aws.region = Some(region.clone());
+        }
+        if let Some(access_key_id) = &config.access_key_id {
+            aws.access_key_id = Some(access_key_id.clone());
+        }
+        if let Some(secret_access_key) = &config.secret_access_key {
+            aws.secret_access_key = Some(secret_access_key.clone());
+        }
+
+        let region = aws
+            .region
+            .clone()
+            .ok_or_else(|| Error::new(ErrorKind::ConfigInvalid, "region is missing"))?;
+
+        let endpoint = match config.endpoint.as_deref() {
+            Some(endpoint) if endpoint.starts_with("http") => endpoint.to_string(),
+            Some(endpoint) => format!("https://{endpoint}"),
+            None => DEFAULT_S3_ENDPOINT.to_string(),
+        };
+        // the bucket is in the path, never in the host
+        let endpoint = endpoint
+            .trim_end_matches('/')
+            .replace(&format!("//{}.", config.name), "//");
+        let endpoint = match endpoint.as_str() {
+            DEFAULT_S3_ENDPOINT => format!("https://s3.{region}.amazonaws.com"),
+            _ => endpoint,
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Some(storage_class) = &config.default_storage_class {
+            headers.insert("x-amz-storage-class", header_value(storage_class)?);
+        }
+
+        let http = reqwest::Client::new();
+        let auth = Auth::Aws(
+            AwsDefaultLoader::new(http.clone(), aws),
+            AwsV4Signer::new("s3", &region),
+        );
+
+        Ok(Self {
+            http,
+            bucket: format!("{endpoint}/{}", config.name),
+            root: root(config.prefix.as_deref()),
+            headers,
+            auth: Arc::new(auth),
+        })
+    }
+}
+
+impl TryFrom<&GCSConfig> for Multipart {
+    type Error = Error;
+
+    fn try_from(config: &GCSConfig) -> Result<Self> {
+        let endpoint = config
+            .endpoint
+            .as_deref()
+            .unwrap_or(DEFAULT_GCS_ENDPOINT)
+            .trim_end_matches('/');
+
+        let mut credentials = GoogleCredentialLoader::default();
+        if let Some(credential) = &config.credential {
+            credentials = credentials.with_content(credential);
+        }
+        if let Some(credential_path) = &config.credential_path {
+            credentials = credentials.with_path(credential_path);
+        }
+
+        let http = reqwest::Client::new();
+        let mut tokens = GoogleTokenLoader::new(GCS_SCOPE, http.clone());
+        if let Ok(Some(credential)) = credentials.load() {
+            tokens = tokens.with_credentials(credential);
+        }
+
+        let mut headers = HeaderMap::new();
+        if let Some(storage_class) = &config.default_storage_class {
+            headers.insert("x-goog-storage-class", header_value(storage_class)?);
+        }
+        if let Some(acl) = &config.predefined_acl {
+            headers.insert("x-goog-acl", header_value(&xml_acl(acl))?);
+        }
+
+        Ok(Self {
+            http,
+            bucket: format!("{endpoint}/{}", config.name),
+            root: root(config.prefix.as_deref()),
+            headers,
+            auth: Arc::new(Auth::Google(tokens, GoogleSigner::new("storage"))),
+        })
+    }
+}
+
+/// Key prefix of a profile, without surrounding slashes
+fn root(prefix: Option<&str>) -> String {
+    prefix.unwrap_or_default().trim_matches('/').to_string()
+}
+
+/// Escapes `value` for the path or query of a request url
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, KEY_ESCAPES).to_string()
+}
+
+/// `predefinedAcl` of the JSON API, e.g. `publicRead`, as the `x-goog-acl`
+/// of the XML API, e.g. `public-read`
+fn xml_acl(acl: &str) -> String {
+    let mut xml = String::with_capacity(acl.len() + 4);
+
+    for c in acl.chars() {
+        if c.is_ascii_uppercase() {
+            xml.push('-');
+        }
+        xml.push(c.to_ascii_lowercase());
+    }
+
+    xml
+}
+
+/// Body of the request completing an upload of `parts`
+fn complete_body(parts: &BTreeMap<u64, String>) -> Result<String> {
+    let parts = parts
+        .iter()
+        .map(|(index, etag)| CompletedPart {
+            part_number: index + 1,
+            etag: etag.clone(),
+        })
+        .collect();
+
+    quick_xml::se::to_string(&CompleteMultipartUpload { parts }).map_err(response_error)
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value)
+        .map_err(|err| Error::new(ErrorKind::ConfigInvalid, "invalid header value").set_source(err))
+}
+
+fn request_error(err: reqwest::Error) -> Error {
+    Error::new(ErrorKind::Unexpected, "multipart request failed")
+        .set_temporary()
+        .set_source(err)
+}
+
+fn response_error<E>(err: E) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    Error::new(ErrorKind::Unexpected, "invalid multipart response").set_source(err)
+}
+
+fn sign_error(err: anyhow::Error) -> Error {
+    Error::new(ErrorKind::PermissionDenied, "cannot sign multipart request").set_source(err)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use percent_encoding::percent_decode_str;
+    use std::{collections::HashMap, sync::Mutex};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// Objects and pending uploads of a [`mock_bucket`]
+    #[derive(Default)]
+    pub(crate) struct MockBucket {
+        pub objects: HashMap<String, Vec<u8>>,
+        pub uploads: HashMap<String, BTreeMap<u64, Vec<u8>>>,
+        created: usize,
+    }
+
+    #[derive(Deserialize)]
+    struct Completed {
+        #[serde(rename = "Part")]
+        parts: Vec<Part>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Part {
+        part_number: u64,
+        #[serde(rename = "ETag")]
+        etag: String,
+    }
+
+    /// Serves the multipart requests of bucket `bucket` on a local port, and
+    /// returns a [`Multipart`] using it
+    pub(crate) async fn mock_bucket() -> (Multipart, Arc<Mutex<MockBucket>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = S3Config {
+            name: "bucket".to_string(),
+            endpoint: Some(format!("http://{}", listener.local_addr().unwrap())),
+            prefix: Some("/root/".to_string()),
+            region: Some("us-east-1".to_string()),
+            access_key_id: Some("access".to_string()),
+            secret_access_key: Some("secret".to_string()),
+            default_storage_class: None,
+        };
+        let bucket = Arc::new(Mutex::new(MockBucket::default()));
+
+        let state = bucket.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    while let Some((line, body)) = read_request(&mut stream).await {
+                        let (status, headers, body) = answer(&state, &line, body);
+                        let head = format!(
+                            "HTTP/1.1 {status} -\r\n{headers}content-length: {}\r\n\r\n",
+                            body.len()
+                        );
+                        let stream = stream.get_mut();
+                        stream.write_all(head.as_bytes()).await.unwrap();
+                        stream.write_all(body.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        ((&config).try_into().unwrap(), bucket)
+    }
+
+    /// Reads the request line and body of the next request
+    async fn read_request<R>(stream: &mut R) -> Option<(String, Vec<u8>)>
+    where
+        R: AsyncBufReadExt + Unpin,
+    {
+        let mut line = String::new();
+        stream
+            .read_line(&mut line)
+            .await
+            .ok()
+            .filter(|read| *read > 0)?;
+
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            stream.read_line(&mut header).await.ok()?;
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().ok()?;
+                }
+            }
+        }
+
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).await.ok()?;
+
+        Some((line, body))
+    }
+
+    fn answer(state: &Mutex<MockBucket>, line: &str, body: Vec<u8>) -> (u16, String, String) {
+        let mut state = state.lock().unwrap();
+        let mut parts = line.split(' ');
+        let (method, target) = (parts.next().unwrap(), parts.next().unwrap());
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let key = path.trim_start_matches("/bucket/").to_string();
+        let params = query
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .collect::<HashMap<_, _>>();
+
+        match (method, params.get("uploadId")) {
+            ("POST", None) => {
+                state.created += 1;
+                let id = format!("upload-{}", state.created);
+                state.uploads.insert(id.clone(), BTreeMap::new());
+
+                let body = format!(
+                    "<InitiateMultipartUploadResult><Bucket>bucket</Bucket><Key>{key}</Key>\
+                     <UploadId>{id}</UploadId></InitiateMultipartUploadResult>"
+                );
+                (200, String::new(), body)
+            }
+            ("PUT", Some(id)) => {
+                let number = params["partNumber"].parse::<u64>().unwrap();
+                let Some(upload) = state.uploads.get_mut(*id) else {
+                    return (
+                        404,
+                        String::new(),
+                        "<Error>NoSuchUpload</Error>".to_string(),
+                    );
+                };
+                upload.insert(number, body);
+
+                (200, format!("etag: \"{number}\"\r\n"), String::new())
+            }
+            ("POST", Some(id)) => {
+                let completed: Completed = quick_xml::de::from_reader(body.as_slice()).unwrap();
+                let Some(mut upload) = state.uploads.remove(*id) else {
+                    return (
+                        404,
+                        String::new(),
+                        "<Error>NoSuchUpload</Error>".to_string(),
+                    );
+                };
+
+                let mut object = vec![];
+                for part in completed.parts {
+                    assert_eq!(format!("\"{}\"", part.part_number), part.etag);
+                    object.extend(upload.remove(&part.part_number).unwrap());
+                }
+                state.objects.insert(key, object);
+
+                (
+                    200,
+                    String::new(),
+                    "<CompleteMultipartUploadResult/>".to_string(),
+                )
+            }
+            ("DELETE", Some(id)) => match state.uploads.remove(*id) {
+                Some(_) => (204, String::new(), String::new()),
+                None => (
+                    404,
+                    String::new(),
+                    "<Error>NoSuchUpload</Error>".to_string(),
+                ),
+            },
+            // listings, one object per page
+            ("GET", None) => {
+                let prefix = params.get("prefix").copied().unwrap_or_default();
+                let prefix = percent_decode_str(prefix).decode_utf8().unwrap();
+                let mut keys = state
+                    .objects
+                    .keys()
+                    .filter(|key| key.starts_with(prefix.as_ref()))
+                    .collect::<Vec<_>>();
+                keys.sort();
+
+                let start = params
+                    .get("continuation-token")
+                    .map_or(0, |token| token.parse().unwrap());
+                let next = if start + 1 < keys.len() {
+                    format!(
+                        "<NextContinuationToken>{}</NextContinuationToken>",
+                        start + 1
+                    )
+                } else {
+                    String::new()
+                };
+                let contents = match keys.get(start) {
+                    Some(key) => format!(
+                        "<Contents><Key>{key}</Key><StorageClass>STANDARD_IA</StorageClass></Contents>"
+                    ),
+                    None => String::new(),
+                };
+
+                (
+                    200,
+                    String::new(),
+                    format!("<ListBucketResult>{next}{contents}</ListBucketResult>"),
+                )
+            }
+            _ => (400, String::new(), String::new()),
+        }
+    }
+
+    #[test]
+    fn should_convert_predefined_acls() {
+        assert_eq!("public-read", xml_acl("publicRead"));
+        assert_eq!(
+            "bucket-owner-full-control",
+            xml_acl("bucketOwnerFullControl")
+        );
+        assert_eq!("private", xml_acl("private"));
+    }
+
+    #[tokio::test]
+    async fn should_upload_parts_out_of_listings() {
+        let (multipart, bucket) = mock_bucket().await;
+
+        let id = multipart
+            .create("/a b.txt", Some("text/plain"))
+            .await
+            .unwrap();
+        let second = multipart
+            .upload_part("/a b.txt", &id, 1, b"world".to_vec())
+            .await
+            .unwrap();
+        let first = multipart
+            .upload_part("/a b.txt", &id, 0, b"hello ".to_vec())
+            .await
+            .unwrap();
+        assert!(bucket.lock().unwrap().objects.is_empty());
+
+        let parts = BTreeMap::from([(0, first), (1, second)]);
+        multipart.complete("/a b.txt", &id, &parts).await.unwrap();
+
+        let bucket = bucket.lock().unwrap();
+        assert_eq!(
+            Some(&b"hello world".to_vec()),
+            bucket.objects.get("root/a%20b.txt")
+        );
+        assert!(bucket.uploads.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_abort_uploads_once() {
+        let (multipart, bucket) = mock_bucket().await;
+
+        let id = multipart.create("a.txt", None).await.unwrap();
+        multipart
+            .upload_part("a.txt", &id, 0, b"hello".to_vec())
+            .await
+            .unwrap();
+
+        multipart.abort("a.txt", &id).await.unwrap();
+        assert!(bucket.lock().unwrap().uploads.is_empty());
+
+        // already gone
+        multipart.abort("a.txt", &id).await.unwrap();
+        let err = multipart
+            .upload_part("a.txt", &id, 1, b"world".to_vec())
+            .await
+            .unwrap_err();
+        assert_eq!(ErrorKind::NotFound, err.kind());
+    }
+}
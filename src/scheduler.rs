@@ -0,0 +1,289 @@
+use futures::{
+    stream::{self, FuturesUnordered},
+    Future, Stream, StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::Arc};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// number of operations in flight at the same time by default
+pub const DEFAULT_TRANSFER_JOBS: usize = 4;
+
+/// number of bytes in flight at the same time by default
+pub const DEFAULT_MAX_IN_FLIGHT_BYTES: u64 = 1024 * 1024 * 1024;
+
+fn default_jobs() -> usize {
+    DEFAULT_TRANSFER_JOBS
+}
+
+fn default_max_in_flight_bytes() -> u64 {
+    DEFAULT_MAX_IN_FLIGHT_BYTES
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransferLimits {
+    /// maximum number of operations in flight
+    #[serde(default = "default_jobs")]
+    pub jobs: usize,
+    /// maximum sum of the sizes of the objects in flight
+    #[serde(rename = "maxInFlightBytes", default = "default_max_in_flight_bytes")]
+    pub max_in_flight_bytes: u64,
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self {
+            jobs: DEFAULT_TRANSFER_JOBS,
+            max_in_flight_bytes: DEFAULT_MAX_IN_FLIGHT_BYTES,
+        }
+    }
+}
+
+/// bytes per permit of the byte budget, which semaphores count in `u32`
+const BYTES_PER_PERMIT: u64 = 1024;
+
+/// Keeps transfers within [`TransferLimits`].
+///
+/// Clones share their budget: every transfer acquires a [`Permit`] before
+/// touching the backend, so that the limits hold across concurrent commands,
+/// multi-file operations and the ranges or parts of a single file alike.
+///
+/// Multi-file operations are also interleaved by size, smallest and largest
+/// first, so that a batch of small files is not stuck behind one huge object.
+/// A transfer heavier than the whole byte budget runs alone rather than never.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    limits: TransferLimits,
+    jobs: Arc<Semaphore>,
+    bytes: Arc<Semaphore>,
+}
+
+/// Held by a transfer while in flight, releasing its share of the
+/// [`TransferLimits`] once dropped
+#[derive(Debug)]
+pub struct Permit {
+    _jobs: OwnedSemaphorePermit,
+    _bytes: OwnedSemaphorePermit,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(TransferLimits::default())
+    }
+}
+
+impl Scheduler {
+    pub fn new(limits: TransferLimits) -> Self {
+        Self {
+            limits,
+            jobs: Arc::new(Semaphore::new(limits.jobs.max(1))),
+            bytes: Arc::new(Semaphore::new(
+                byte_permits(limits.max_in_flight_bytes) as usize
+            )),
+        }
+    }
+
+    pub fn limits(&self) -> TransferLimits {
+        self.limits
+    }
+
+    /// Waits until a transfer of `size` bytes fits within the limits.
+    ///
+    /// Transfers of unknown size, such as streams, pass `0` and only count
+    /// against the number of jobs. Permits are acquired by single transfers
+    /// only, never while holding another one, so that they cannot deadlock.
+    pub async fn acquire(&self, size: u64) -> Permit {
+        let budget = byte_permits(self.limits.max_in_flight_bytes);
+        let weight = size.div_ceil(BYTES_PER_PERMIT).min(budget as u64) as u32;
+
+        // SAFETY: the semaphores are never closed
+        let jobs = self.jobs.clone().acquire_owned().await.unwrap();
+        let bytes = self.bytes.clone().acquire_many_owned(weight).await.unwrap();
+
+        Permit {
+            _jobs: jobs,
+            _bytes: bytes,
+        }
+    }
+
+    /// Runs every `(size, operation)` pair and returns the outputs in
+    /// completion order.
+    pub async fn run<I, F, T>(&self, operations: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = (u64, F)>,
+        F: Future<Output = T>,
+    {
+        self.stream(operations).collect().await
+    }
+
+    /// Same as [`Scheduler::run`], yielding each output as soon as its
+    /// operation completes.
+    pub fn stream<I, F, T>(&self, operations: I) -> impl Stream<Item = T>
+    where
+        I: IntoIterator<Item = (u64, F)>,
+        F: Future<Output = T>,
+    {
+        let jobs = self.limits.jobs.max(1);
+        let budget = self.limits.max_in_flight_bytes.max(1);
+
+        let pending = interleave(
+            operations
+                .into_iter()
+                .map(|(size, operation)| (size.min(budget), operation))
+                .collect(),
+        );
+        let running = FuturesUnordered::new();
+        // bytes in flight, and how many times the head of the queue has been
+        // overtaken
+        let state = (pending, running, 0, 0);
+
+        stream::unfold(state, move |state| async move {
+            let (mut pending, mut running, mut in_flight_bytes, mut overtaken) = state;
+
+            while running.len() < jobs {
+                let available = budget - in_flight_bytes;
+                let next = match pending.front() {
+                    None => break,
+                    Some((size, _)) if *size <= available => Some(0),
+                    // smaller operations may overtake the head of the queue a
+                    // bounded number of times, so that it cannot starve
+                    Some(_) if overtaken < jobs => {
+                        pending.iter().position(|(size, _)| *size <= available)
+                    }
+                    Some(_) => None,
+                };
+                let Some(next) = next else {
+                    break;
+                };
+
+                overtaken = if next == 0 { 0 } else { overtaken + 1 };
+
+                // SAFETY: `next` is a valid index of `pending`
+                let (size, operation) = pending.remove(next).unwrap();
+                in_flight_bytes += size;
+                running.push(sized(size, operation));
+            }
+
+            let (size, output) = running.next().await?;
+            in_flight_bytes -= size;
+
+            Some((output, (pending, running, in_flight_bytes, overtaken)))
+        })
+    }
+}
+
+/// Number of permits of a budget of `bytes` bytes
+fn byte_permits(bytes: u64) -> u32 {
+    bytes
+        .div_ceil(BYTES_PER_PERMIT)
+        .clamp(1, u64::from(u32::MAX).min(Semaphore::MAX_PERMITS as u64)) as u32
+}
+
+/// Pairs the output of `operation` with its size
+async fn sized<F: Future>(size: u64, operation: F) -> (u64, F::Output) {
+    (size, operation.await)
+}
+
+/// Orders operations as smallest, largest, second smallest, second largest...
+fn interleave<F>(mut operations: Vec<(u64, F)>) -> VecDeque<(u64, F)> {
+    operations.sort_by_key(|(size, _)| *size);
+
+    let mut operations = VecDeque::from(operations);
+    let mut interleaved = VecDeque::with_capacity(operations.len());
+
+    while let Some(smallest) = operations.pop_front() {
+        interleaved.push_back(smallest);
+
+        if let Some(largest) = operations.pop_back() {
+            interleaved.push_back(largest);
+        }
+    }
+
+    interleaved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn should_interleave_small_and_large_operations() {
+        let operations = [5, 1, 4, 2, 3].into_iter().map(|size| (size, ())).collect();
+
+        let order = interleave(operations)
+            .into_iter()
+            .map(|(size, _)| size)
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![1, 5, 2, 4, 3], order);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn should_respect_transfer_limits() {
+        let scheduler = Scheduler::new(TransferLimits {
+            jobs: 3,
+            max_in_flight_bytes: 100,
+        });
+        let jobs = Arc::new(AtomicUsize::new(0));
+        let bytes = Arc::new(AtomicU64::new(0));
+        let peaks = Arc::new((AtomicUsize::new(0), AtomicU64::new(0)));
+
+        let operations = [10, 90, 500, 20, 30, 60, 1, 1, 1, 70]
+            .into_iter()
+            .map(|size: u64| {
+                let (jobs, bytes, peaks) = (jobs.clone(), bytes.clone(), peaks.clone());
+                let weight = size.min(100);
+
+                (size, async move {
+                    let running = jobs.fetch_add(1, Ordering::SeqCst) + 1;
+                    let in_flight = bytes.fetch_add(weight, Ordering::SeqCst) + weight;
+                    peaks.0.fetch_max(running, Ordering::SeqCst);
+                    peaks.1.fetch_max(in_flight, Ordering::SeqCst);
+
+                    tokio::task::yield_now().await;
+
+                    jobs.fetch_sub(1, Ordering::SeqCst);
+                    bytes.fetch_sub(weight, Ordering::SeqCst);
+                    size
+                })
+            });
+
+        let mut outputs = scheduler.run(operations).await;
+        outputs.sort();
+
+        assert_eq!(vec![1, 1, 1, 10, 20, 30, 60, 70, 90, 500], outputs);
+        assert!(peaks.0.load(Ordering::SeqCst) <= 3);
+        assert!(peaks.1.load(Ordering::SeqCst) <= 100);
+    }
+
+    #[tokio::test]
+    async fn should_share_limits_between_clones() {
+        let scheduler = Scheduler::new(TransferLimits {
+            jobs: 2,
+            max_in_flight_bytes: 10 * BYTES_PER_PERMIT,
+        });
+        let clone = scheduler.clone();
+        let timeout = std::time::Duration::from_millis(50);
+
+        let first = scheduler.acquire(6 * BYTES_PER_PERMIT).await;
+        // over the remaining budget, but no heavier than the whole of it
+        assert!(
+            tokio::time::timeout(timeout, clone.acquire(5 * BYTES_PER_PERMIT))
+                .await
+                .is_err()
+        );
+
+        let second = clone.acquire(BYTES_PER_PERMIT).await;
+        // over the remaining jobs
+        assert!(tokio::time::timeout(timeout, scheduler.acquire(0))
+            .await
+            .is_err());
+
+        drop((first, second));
+        let _whole = clone.acquire(u64::MAX).await;
+    }
+}
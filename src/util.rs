@@ -1,6 +1,9 @@
-use crate::client::StatEntry;
+use crate::client::{ObjectMeta, TransferReport, UploadSession, Usage};
+use clap::ValueEnum;
+use colored::Colorize;
 use opendal::EntryMode;
-use prettytable::{format, row, Table};
+use prettytable::{format, row, Cell, Row, Table};
+use std::{cmp::Ordering, io::Write, time::Duration};
 
 fn parse_content_length(input: &str, raw: bool) -> String {
     if raw || input.is_empty() {
@@ -28,6 +31,56 @@ fn parse_content_length(input: &str, raw: bool) -> String {
     }
 }
 
+/// Parses sizes like `512`, `100MB` or `1.5GiB` into bytes
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+    let value = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid size '{input}'"))?;
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_u64.pow(2),
+        "gb" => 1000_u64.pow(3),
+        "tb" => 1000_u64.pow(4),
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        unit => return Err(format!("unknown size unit '{unit}'")),
+    };
+
+    Ok((value * multiplier as f64).round() as u64)
+}
+
+/// Parses ages like `90s`, `12h` or `30d` into a duration
+pub fn parse_age(input: &str) -> Result<Duration, String> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+    let value = value
+        .parse::<u64>()
+        .map_err(|_| format!("invalid age '{input}'"))?;
+    let seconds = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "" => return Err(format!("missing unit (s, m, h, d, w) in age '{input}'")),
+        unit => return Err(format!("unknown age unit '{unit}'")),
+    };
+
+    value
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("age '{input}' is too large"))
+}
+
 pub fn log_profiles_table(mut items: Vec<&String>, current: Option<&str>) {
     let mut table = Table::new();
 
@@ -47,27 +100,332 @@ pub fn log_profiles_table(mut items: Vec<&String>, current: Option<&str>) {
     table.print_tty(true).unwrap();
 }
 
-pub fn log_files_table(items: &[StatEntry], raw: bool, should_paginate: bool) {
+/// Metadata an entry listing can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortBy {
+    Name,
+    Size,
+    Modified,
+    /// content type
+    Type,
+}
+
+/// Sorts `items` by `by`, ties being sorted by path, keeping directories
+/// before files when `dirs_first`
+pub fn sort_entries(items: &mut [ObjectMeta], by: SortBy, reverse: bool, dirs_first: bool) {
+    items.sort_by(|a, b| {
+        let ordering = match by {
+            SortBy::Name => Ordering::Equal,
+            SortBy::Size => a.content_length.cmp(&b.content_length),
+            SortBy::Modified => a.last_modified.cmp(&b.last_modified),
+            SortBy::Type => a.content_type.cmp(&b.content_type),
+        }
+        .then_with(|| a.path.cmp(&b.path));
+        let ordering = if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+
+        match dirs_first {
+            true => b.is_dir().cmp(&a.is_dir()).then(ordering),
+            false => ordering,
+        }
+    });
+}
+
+/// Metadata shown by an entry listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Name,
+    ContentType,
+    Size,
+    /// file or dir
+    Type,
+    Modified,
+    Etag,
+}
+
+pub const DEFAULT_COLUMNS: [Column; 4] = [
+    Column::Name,
+    Column::ContentType,
+    Column::Size,
+    Column::Type,
+];
+
+impl Column {
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::ContentType => "content-type",
+            Column::Size => "size",
+            Column::Type => "type",
+            Column::Modified => "last-modified",
+            Column::Etag => "etag",
+        }
+    }
+
+    fn cell(&self, root: &str, item: &ObjectMeta, raw: bool) -> Cell {
+        let (text, style) = match (self, item.is_dir()) {
+            (Column::Name, true) => (item.relative_path(root).to_string(), "Fm"),
+            (Column::Name, false) => (item.relative_path(root).to_string(), "Fw"),
+            (Column::Type, true) => (String::from("dir"), "Fmb"),
+            (Column::Type, false) => (String::from("file"), "Fbb"),
+            (_, true) => (String::new(), ""),
+            (Column::ContentType, false) => (item.content_type.clone().unwrap_or_default(), "Fbb"),
+            (Column::Size, false) => {
+                let size = item
+                    .content_length
+                    .map(|len| len.to_string())
+                    .unwrap_or_default();
+                (parse_content_length(&size, raw), "Fbb")
+            }
+            (Column::Modified, false) => {
+                let modified = item
+                    .last_modified
+                    .map(|modified| modified.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                (modified, "Fbb")
+            }
+            (Column::Etag, false) => (item.etag.clone().unwrap_or_default(), "Fbb"),
+        };
+
+        Cell::new(&text).style_spec(style)
+    }
+}
+
+/// Prints listed entries with their path relative to folder `root`
+pub fn log_files_table(
+    root: &str,
+    items: &[ObjectMeta],
+    columns: &[Column],
+    raw: bool,
+    should_paginate: bool,
+) {
     let mut table = Table::new();
 
     table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
 
-    table.set_titles(row!["", Fgb->"name", Fgb->"content-type", Fgb->"size", Fgb->"type"]);
+    let titles = columns
+        .iter()
+        .map(|column| Cell::new(column.title()).style_spec("Fgb"));
+    table.set_titles(Row::new(
+        std::iter::once(Cell::new("")).chain(titles).collect(),
+    ));
     for (line, item) in items.iter().enumerate() {
-        let line = line + 1;
-        match item.3 {
-            EntryMode::FILE => {
-                table.add_row(row![Fw-> line, Fw->item.0,Fbb->item.1,Fbb->parse_content_length(&item.2, raw),Fbb->"file"]);
-            }
-            EntryMode::DIR => {
-                table.add_row(row![Fw-> line, Fm->item.0, "", "", Fmb->"dir"]);
+        if item.mode == EntryMode::Unknown {
+            continue;
+        }
+
+        let line = Cell::new(&(line + 1).to_string()).style_spec("Fw");
+        let cells = columns.iter().map(|column| column.cell(root, item, raw));
+        table.add_row(Row::new(std::iter::once(line).chain(cells).collect()));
+    }
+
+    if !should_paginate {
+        let cells = columns.iter().enumerate().map(|(index, _)| match index {
+            0 => Cell::new("...").style_spec("Fm"),
+            _ => Cell::new(""),
+        });
+        table.add_row(Row::new(
+            std::iter::once(Cell::new("")).chain(cells).collect(),
+        ));
+    }
+
+    table.print_tty(true).unwrap();
+}
+
+/// Prints the entries of a depth-first walk of folder `root` as a tree.
+///
+/// Folders show the total size of the files walked below them, folders
+/// beyond `max_depth` have not been walked and show no size.
+pub fn log_tree(root: &str, entries: &[ObjectMeta], max_depth: Option<usize>, raw: bool) {
+    let depths = entries
+        .iter()
+        .map(|entry| entry.depth(root))
+        .collect::<Vec<_>>();
+
+    let mut total = 0;
+    let mut totals = vec![0; entries.len()];
+    let mut folders: Vec<usize> = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        while folders
+            .last()
+            .is_some_and(|folder| depths[*folder] >= depths[index])
+        {
+            folders.pop();
+        }
+
+        match entry.content_length {
+            _ if entry.is_dir() => folders.push(index),
+            Some(size) => {
+                total += size;
+                for folder in folders.iter() {
+                    totals[*folder] += size;
+                }
             }
-            EntryMode::Unknown => {}
+            None => {}
+        }
+    }
+
+    // an entry is the last of its folder when no sibling follows it before
+    // the walk goes back up
+    let mut is_last = vec![false; entries.len()];
+    let mut followed = vec![];
+    for index in (0..entries.len()).rev() {
+        followed.resize(depths[index] + 1, false);
+        is_last[index] = !followed[depths[index]];
+        followed[depths[index]] = true;
+    }
+
+    let size = |size: u64| parse_content_length(&size.to_string(), raw);
+
+    println!("{} ({})", root.bold().magenta(), size(total));
+
+    // whether each ancestor of the entry is the last of its folder
+    let mut ancestors: Vec<bool> = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        ancestors.truncate(depths[index] - 1);
+
+        let indent = ancestors
+            .iter()
+            .map(|is_last| if *is_last { "    " } else { "│   " })
+            .collect::<String>();
+        let branch = if is_last[index] {
+            "└── "
+        } else {
+            "├── "
         };
+
+        if !entry.is_dir() {
+            let length = entry.content_length.map(size).unwrap_or_default();
+            println!("{indent}{branch}{} ({})", entry.name, length.bold().blue());
+        } else if depths[index] < max_depth.unwrap_or(usize::MAX) {
+            let length = size(totals[index]);
+            println!(
+                "{indent}{branch}{} ({})",
+                entry.name.bold().magenta(),
+                length.bold().blue()
+            );
+        } else {
+            println!("{indent}{branch}{}", entry.name.bold().magenta());
+        }
+
+        ancestors.push(is_last[index]);
     }
+}
 
-    if !should_paginate {
-        table.add_row(row![Fw -> "", Fm-> "...", "", "", ""]);
+/// Prints every metadata field of `item`, empty ones aside
+pub fn log_stat_table(item: &ObjectMeta, raw: bool) {
+    let mut table = Table::new();
+
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.set_titles(row![Fgb->"field", Fgb->"value"]);
+    let mode = if item.is_dir() { "dir" } else { "file" };
+    let size = item
+        .content_length
+        .map(|len| parse_content_length(&len.to_string(), raw));
+    let modified = item.last_modified.map(|modified| modified.to_rfc3339());
+    let fields = [
+        ("path", Some(item.path.clone())),
+        ("type", Some(mode.to_string())),
+        ("size", size),
+        ("content-type", item.content_type.clone()),
+        ("etag", item.etag.clone()),
+        ("content-md5", item.content_md5.clone()),
+        ("last-modified", modified),
+        ("cache-control", item.cache_control.clone()),
+        ("content-disposition", item.content_disposition.clone()),
+        ("version", item.version.clone()),
+    ];
+    for (field, value) in fields {
+        if let Some(value) = value {
+            table.add_row(row![Fw->field, Fbb->value]);
+        }
+    }
+
+    let mut user_metadata = item.user_metadata.iter().collect::<Vec<_>>();
+    user_metadata.sort();
+    for (key, value) in user_metadata {
+        table.add_row(row![Fm->format!("metadata.{key}"), Fbb->value]);
+    }
+
+    table.print_tty(true).unwrap();
+}
+
+/// Prints the planned `(source, destination, size)` moves
+pub fn log_moves_table(items: &[(String, String, u64)], raw: bool) {
+    let mut table = Table::new();
+
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.set_titles(row![Fgb->"source", Fgb->"destination", Fgb->"size"]);
+    for (src, dest, size) in items {
+        table.add_row(row![
+            Fw->src,
+            Fm->dest,
+            Fbb->parse_content_length(&size.to_string(), raw)
+        ]);
+    }
+
+    table.print_tty(true).unwrap();
+}
+
+pub fn log_usage_table(items: &[Usage], raw: bool) {
+    let mut table = Table::new();
+
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.set_titles(row![Fgb->"prefix", Fgb->"objects", Fgb->"size"]);
+    for item in items {
+        table.add_row(row![
+            Fm->item.prefix,
+            Fw->item.objects,
+            Fbb->parse_content_length(&item.size.to_string(), raw)
+        ]);
+    }
+
+    table.print_tty(true).unwrap();
+}
+
+pub fn log_upload_sessions_table(mut items: Vec<&UploadSession>) {
+    let mut table = Table::new();
+
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.set_titles(
+        row![Fgb->"id", Fgb->"profile", Fgb->"source", Fgb->"destination", Fgb->"parts"],
+    );
+    items.sort_by(|a, b| a.id.cmp(&b.id));
+    for item in items {
+        table.add_row(row![
+            Fw->item.id,
+            Fbb->item.profile,
+            Fw->item.src.display(),
+            Fm->item.dest,
+            Fbb->format!("{}/{}", item.completed.len(), item.parts())
+        ]);
+    }
+
+    table.print_tty(true).unwrap();
+}
+
+pub fn log_transfers_table(report: &TransferReport) {
+    let mut table = Table::new();
+
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+
+    table.set_titles(row![Fgb->"source", Fgb->"destination", Fgb->"result"]);
+    for transfer in report.transfers.iter() {
+        match &transfer.result {
+            Ok(bytes) => {
+                table.add_row(row![Fw->transfer.src, Fm->transfer.dest, Fgb->parse_content_length(&bytes.to_string(), false)]);
+            }
+            Err(err) => {
+                table.add_row(row![Fw->transfer.src, Fm->transfer.dest, Frb->err]);
+            }
+        }
     }
 
     table.print_tty(true).unwrap();
@@ -94,6 +452,18 @@ pub async fn what_next() -> NextAction {
     }
 }
 
+/// Asks `question` on stderr, keeping stdout for the command output,
+/// answered with 'y' or 'yes' on stdin
+pub fn confirm(question: &str) -> bool {
+    eprint!("{question} [y/N] ");
+    let _ = std::io::stderr().flush();
+
+    let mut input = String::new();
+    let _ = std::io::stdin().read_line(&mut input);
+
+    matches!(input.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 #[macro_export]
 macro_rules! opendal_builder {
     ($builder:expr, $( $opt:expr => $method:ident ),* ) => {{
@@ -108,3 +478,64 @@ macro_rules! opendal_builder {
         builder
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_sizes() {
+        assert_eq!(Ok(512), parse_size("512"));
+        assert_eq!(Ok(100_000_000), parse_size("100MB"));
+        assert_eq!(Ok(1536), parse_size("1.5KiB"));
+        assert_eq!(Ok(2 * 1024 * 1024 * 1024), parse_size("2gib"));
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10XB").is_err());
+    }
+
+    #[test]
+    fn should_sort_entries_with_directories_first() {
+        let entry = |path: &str, mode: EntryMode, size: Option<u64>| ObjectMeta {
+            path: path.to_string(),
+            name: path.to_string(),
+            mode,
+            content_length: size,
+            content_type: None,
+            last_modified: None,
+            etag: None,
+            content_md5: None,
+            cache_control: None,
+            content_disposition: None,
+            version: None,
+            user_metadata: Default::default(),
+        };
+        let mut items = vec![
+            entry("b.txt", EntryMode::FILE, Some(1)),
+            entry("z/", EntryMode::DIR, None),
+            entry("a.txt", EntryMode::FILE, Some(3)),
+            entry("c.txt", EntryMode::FILE, Some(2)),
+            entry("y/", EntryMode::DIR, None),
+        ];
+        let paths = |items: &[ObjectMeta]| {
+            items
+                .iter()
+                .map(|item| item.path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        sort_entries(&mut items, SortBy::Size, true, true);
+        assert_eq!(vec!["z/", "y/", "a.txt", "c.txt", "b.txt"], paths(&items));
+
+        sort_entries(&mut items, SortBy::Name, false, false);
+        assert_eq!(vec!["a.txt", "b.txt", "c.txt", "y/", "z/"], paths(&items));
+    }
+
+    #[test]
+    fn should_parse_ages() {
+        assert_eq!(Ok(Duration::from_secs(90)), parse_age("90s"));
+        assert_eq!(Ok(Duration::from_secs(30 * 24 * 60 * 60)), parse_age("30d"));
+        assert!(parse_age("30").is_err());
+        assert!(parse_age("1y").is_err());
+        assert!(parse_age("18446744073709551615w").is_err());
+    }
+}